@@ -0,0 +1,278 @@
+//! Exact odds: where the dealer's hand ends up from any up card and any shoe.
+//!
+//! Only a card's point value matters to the play, so a [`Shoe`] is ten counts,
+//! ace first, rather than a list of cards. From it [`dealer_odds`] works out
+//! the chance of each way the dealer can finish by following
+//! `rules::dealer_should_hit` through every draw, either as if the shoe never
+//! ran down ([`Deal::Infinite`]) or card by card as it does ([`Deal::Finite`]).
+//! Basic strategy and every expected value in the crate start from here.
+
+use crate::card::{Card, Deck, Rank, Suit};
+use crate::hand::Hand;
+use crate::rules;
+use std::collections::HashMap;
+
+/// One card of each point value, ace first, standing in for its whole value
+/// class: a jack draws exactly like a ten.
+pub const VALUES: [Rank; 10] = [
+    Rank::Ace,
+    Rank::Two,
+    Rank::Three,
+    Rank::Four,
+    Rank::Five,
+    Rank::Six,
+    Rank::Seven,
+    Rank::Eight,
+    Rank::Nine,
+    Rank::Ten,
+];
+
+/// Where `rank` sits in [`VALUES`].
+pub fn value_of(rank: Rank) -> usize {
+    match rank {
+        Rank::Ace => 0,
+        other => other.points() as usize - 1,
+    }
+}
+
+pub(crate) fn with_card(hand: &Hand, value: usize) -> Hand {
+    let mut next = hand.clone();
+    next.push(Card { rank: VALUES[value], suit: Suit::Spades });
+    next
+}
+
+/// The cards left to draw, counted by point value in the order of [`VALUES`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Shoe {
+    counts: [u16; 10],
+}
+
+impl Shoe {
+    /// `packs` full packs: four of each value and sixteen tens to a pack.
+    pub fn new(packs: u8) -> Self {
+        let mut counts = [4 * u16::from(packs); 10];
+        counts[9] *= 4;
+        Shoe { counts }
+    }
+
+    /// Counts by value, ace first.
+    pub fn from_counts(counts: [u16; 10]) -> Self {
+        Shoe { counts }
+    }
+
+    /// What is left in a dealing shoe.
+    pub fn of(deck: &Deck) -> Self {
+        let mut shoe = Shoe { counts: [0; 10] };
+        deck.cards().iter().for_each(|card| shoe.counts[value_of(card.rank)] += 1);
+        shoe
+    }
+
+    pub fn count(&self, rank: Rank) -> u16 {
+        self.counts[value_of(rank)]
+    }
+
+    pub fn len(&self) -> u32 {
+        self.counts.iter().map(|&count| u32::from(count)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Takes a card seen elsewhere out of the shoe. A card the shoe has none
+    /// of left is ignored, so that cards in view can be removed without
+    /// checking that they came from this shoe.
+    pub fn remove(&mut self, rank: Rank) {
+        let count = &mut self.counts[value_of(rank)];
+        *count = count.saturating_sub(1);
+    }
+
+    /// The chance that the next card is of each value, ace first.
+    pub fn odds(&self) -> [f64; 10] {
+        let left = f64::from(self.len().max(1));
+        self.counts.map(|count| f64::from(count) / left)
+    }
+
+    /// The shoe after a card of `value` is drawn, by the rules of `deal`.
+    pub(crate) fn after(&self, value: usize, deal: Deal) -> Shoe {
+        let mut next = *self;
+        if deal == Deal::Finite {
+            next.counts[value] -= 1;
+        }
+        next
+    }
+}
+
+/// How cards come out of the shoe while the dealer draws.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Deal {
+    /// Every card at the odds of the shoe as given, as if it never ran down.
+    /// Given a full shoe, that is the usual infinite-deck model.
+    Infinite,
+    /// Each card drawn leaves the shoe: exact for the shoe as it stands.
+    Finite,
+}
+
+/// The chance of each way the dealer's hand can end. The seven add up to one.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DealerOdds {
+    /// Standing on 17, 18, 19, 20 and 21, a natural aside.
+    pub totals: [f64; 5],
+    pub natural: f64,
+    pub bust: f64,
+}
+
+impl DealerOdds {
+    /// The same hand once a peek has found no natural under the up card, as
+    /// at an American table before anyone acts.
+    pub fn given_no_natural(&self) -> DealerOdds {
+        let rest = 1.0 - self.natural;
+        if rest <= 0.0 {
+            return DealerOdds::default();
+        }
+        DealerOdds {
+            totals: self.totals.map(|chance| chance / rest),
+            natural: 0.0,
+            bust: self.bust / rest,
+        }
+    }
+
+    /// The chance of standing on `total`, 17 to 21.
+    pub fn standing_on(&self, total: u16) -> f64 {
+        match total {
+            17..=21 => self.totals[usize::from(total - 17)],
+            _ => 0.0,
+        }
+    }
+}
+
+/// Where the dealer ends up showing `up` with `shoe` left to draw from, the
+/// up card already out of it. The hole card is drawn like any other, so a
+/// natural is one of the outcomes; see [`DealerOdds::given_no_natural`] for
+/// the odds after a peek. A finite shoe too short to finish the hand leaves
+/// out the draws it cannot make, so its odds add up to less than one.
+pub fn dealer_odds(up: Card, shoe: &Shoe, hits_soft_17: bool, deal: Deal) -> DealerOdds {
+    let mut dealer = Dealer { hits_soft_17, deal, memo: HashMap::new() };
+    let up = Hand::from_cards(vec![up]);
+    let mut odds = DealerOdds::default();
+    for (value, chance) in draws(shoe) {
+        let hand = with_card(&up, value);
+        if hand.is_blackjack() {
+            odds.natural += chance;
+            continue;
+        }
+        let finals = dealer.finals(&hand, &shoe.after(value, deal));
+        odds.totals.iter_mut().zip(&finals[..5]).for_each(|(odds, drawn)| *odds += chance * drawn);
+        odds.bust += chance * finals[5];
+    }
+    odds
+}
+
+/// Each value the shoe still holds, with the chance of drawing it.
+pub(crate) fn draws(shoe: &Shoe) -> impl Iterator<Item = (usize, f64)> {
+    let odds = shoe.odds();
+    (0..10).filter(move |&value| odds[value] > 0.0).map(move |value| (value, odds[value]))
+}
+
+/// The dealer's draws past the hole card. Only the total decides what the
+/// dealer does, so each total is worked out once per shoe it is drawn from;
+/// an infinite deal never changes the shoe, so that is once in all.
+struct Dealer {
+    hits_soft_17: bool,
+    deal: Deal,
+    memo: HashMap<(Shoe, u16, bool), [f64; 6]>,
+}
+
+impl Dealer {
+    /// Standing on 17 to 21, then bust.
+    fn finals(&mut self, hand: &Hand, shoe: &Shoe) -> [f64; 6] {
+        let total = hand.total();
+        let key = (*shoe, total.value, total.soft);
+        if let Some(&finals) = self.memo.get(&key) {
+            return finals;
+        }
+        let mut finals = [0.0; 6];
+        if rules::dealer_should_hit(hand, self.hits_soft_17) {
+            for (value, chance) in draws(shoe) {
+                let drawn = self.finals(&with_card(hand, value), &shoe.after(value, self.deal));
+                finals.iter_mut().zip(drawn).for_each(|(finals, drawn)| *finals += chance * drawn);
+            }
+        } else if total.value > 21 {
+            finals[5] = 1.0;
+        } else {
+            finals[usize::from(total.value - 17)] = 1.0;
+        }
+        self.memo.insert(key, finals);
+        finals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Rank::*;
+    use crate::hand::fixtures::card;
+
+    fn close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 5e-5, "{} is not {}", actual, expected);
+    }
+
+    fn sum(odds: &DealerOdds) -> f64 {
+        odds.totals.iter().sum::<f64>() + odds.natural + odds.bust
+    }
+
+    #[test]
+    fn an_infinite_shoe_gives_the_published_dealer_table() {
+        let ten = dealer_odds(card(Ten), &Shoe::new(1), false, Deal::Infinite);
+        let expected = [0.1114, 0.1114, 0.1114, 0.3422, 0.0345];
+        ten.totals.iter().zip(expected).for_each(|(&actual, expected)| close(actual, expected));
+        close(ten.natural, 0.0769);
+        close(ten.bust, 0.2121);
+        let ace = dealer_odds(card(Ace), &Shoe::new(8), false, Deal::Infinite);
+        close(ace.natural, 0.3077);
+        close(ace.bust, 0.1153);
+        close(dealer_odds(card(Six), &Shoe::new(1), false, Deal::Infinite).bust, 0.4232);
+    }
+
+    #[test]
+    fn a_finite_shoe_is_drawn_without_replacement_to_the_last_card() {
+        let shoe = Shoe::from_counts([0, 0, 0, 0, 1, 1, 0, 0, 0, 1]);
+        let odds = dealer_odds(card(King), &shoe, false, Deal::Finite);
+        let third = 1.0 / 3.0;
+        assert_eq!(odds.totals, [0.0, 0.0, 0.0, third, third]);
+        assert_eq!((odds.natural, odds.bust), (0.0, third));
+        assert_ne!(dealer_odds(card(King), &shoe, false, Deal::Infinite), odds);
+
+        for rank in VALUES {
+            let mut shoe = Shoe::new(1);
+            shoe.remove(rank);
+            for deal in [Deal::Finite, Deal::Infinite] {
+                let odds = dealer_odds(card(rank), &shoe, true, deal);
+                assert!((sum(&odds) - 1.0).abs() < 1e-12, "{:?} up, {:?}", rank, deal);
+            }
+        }
+    }
+
+    #[test]
+    fn hitting_soft_17_trades_seventeens_for_busts_and_better_hands() {
+        let s17 = dealer_odds(card(Six), &Shoe::new(6), false, Deal::Finite);
+        let h17 = dealer_odds(card(Six), &Shoe::new(6), true, Deal::Finite);
+        assert!(h17.standing_on(17) < s17.standing_on(17));
+        assert!(h17.bust > s17.bust && h17.standing_on(21) > s17.standing_on(21));
+        assert_eq!(h17.standing_on(16), 0.0);
+    }
+
+    #[test]
+    fn a_peek_takes_the_natural_out_and_scales_up_the_rest() {
+        let ace = dealer_odds(card(Ace), &Shoe::new(6), false, Deal::Infinite);
+        let peeked = ace.given_no_natural();
+        assert_eq!(peeked.natural, 0.0);
+        assert!((sum(&peeked) - 1.0).abs() < 1e-12);
+        close(peeked.bust, ace.bust / (1.0 - ace.natural));
+
+        assert_eq!(Shoe::of(&Deck::new(2)), Shoe::new(2));
+        let mut empty = Shoe::from_counts([0; 10]);
+        empty.remove(Ace);
+        assert!(empty.is_empty() && Shoe::new(1).count(Queen) == 16);
+    }
+}
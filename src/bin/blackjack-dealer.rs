@@ -0,0 +1,62 @@
+//! Prints where the dealer's hand ends up from every up card.
+//!
+//! ```text
+//! blackjack-dealer [--infinite] [--peek]
+//! ```
+//!
+//! The packs and the soft 17 rule come from the same `BLACKJACK_*` variables
+//! the server reads. By default each up card is taken out of a full shoe and
+//! the dealer draws from what is left, card by card; `--infinite` draws every
+//! card at a full shoe's odds instead. `--peek` shows the odds once the dealer
+//! has checked for a natural and found none.
+
+use blackjack_rust::analysis::{self, Deal, Shoe, VALUES};
+use blackjack_rust::card::{Card, Suit};
+use blackjack_rust::config::Config;
+use std::env;
+use std::error::Error;
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("blackjack-dealer: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let (mut deal, mut peek) = (Deal::Finite, false);
+    for flag in env::args().skip(1) {
+        match flag.as_str() {
+            "--infinite" => deal = Deal::Infinite,
+            "--peek" => peek = true,
+            _ => return Err(format!("unknown option {}", flag).into()),
+        }
+    }
+    let rules = Config::rules_from_env()?;
+    let soft_17 = if rules.dealer_hits_soft_17 { "H17" } else { "S17" };
+    let drawn = match deal {
+        Deal::Finite => format!("{} packs, drawn card by card", rules.packs),
+        Deal::Infinite => "an infinite shoe".to_string(),
+    };
+    let peeked = if peek { ", once a peek has found no natural" } else { "" };
+    println!("The dealer's final hand, {}, {}{}", soft_17, drawn, peeked);
+    println!("up        17       18       19       20       21  natural     bust");
+    // Ace last, as charts print it.
+    for rank in VALUES[1..].iter().chain(&VALUES[..1]) {
+        let up = Card { rank: *rank, suit: Suit::Spades };
+        let mut shoe = Shoe::new(rules.packs);
+        if deal == Deal::Finite {
+            shoe.remove(up.rank);
+        }
+        let mut odds = analysis::dealer_odds(up, &shoe, rules.dealer_hits_soft_17, deal);
+        if peek {
+            odds = odds.given_no_natural();
+        }
+        let mut row = format!("{:<3}", rank.to_string());
+        for chance in odds.totals.iter().chain([&odds.natural, &odds.bust]) {
+            row += &format!("{:>8.3}%", 100.0 * chance);
+        }
+        println!("{}", row);
+    }
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! Prints what every move on one hand is worth.
+//!
+//! ```text
+//! blackjack-ev <hand> <up card> [--seen <cards>] [--infinite]
+//! ```
+//!
+//! Cards are ranks — A, 2 to 10 or T, J, Q, K — run together or apart, the
+//! up card last, so `T6 9`, `10 6 9` and `k,6 9` are the same question. The
+//! rules come from the same `BLACKJACK_*` variables the server reads. The
+//! hand, the up card and any `--seen` cards come out of a full shoe, and every
+//! card after them is drawn from what is left; `--infinite` draws every card
+//! at the odds the cards in view leave instead, which is how the server's
+//! hints and grades see it.
+
+use blackjack_rust::analysis::{Deal, Shoe};
+use blackjack_rust::card::{Card, Rank, Suit};
+use blackjack_rust::config::Config;
+use blackjack_rust::hand::Hand;
+use blackjack_rust::rules::Surrender;
+use blackjack_rust::strategy::{self, Options};
+use std::env;
+use std::error::Error;
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("blackjack-ev: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let (mut deal, mut seen, mut cards) = (Deal::Finite, Vec::new(), Vec::new());
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--infinite" => deal = Deal::Infinite,
+            "--seen" => seen = ranks(&args.next().ok_or("--seen needs the cards seen")?)?,
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag).into()),
+            _ => cards.push(ranks(&arg)?),
+        }
+    }
+    let up = match cards.pop().as_deref() {
+        Some(&[up]) => card(up),
+        Some(_) => return Err("the dealer shows one up card, given last".into()),
+        None => return Err("give the hand, then the dealer's up card".into()),
+    };
+    let hand = Hand::from_cards(cards.into_iter().flatten().map(card).collect());
+    if hand.cards().len() < 2 || hand.is_bust() {
+        return Err("a hand to play has two cards or more and is not bust".into());
+    }
+
+    let rules = Config::rules_from_env()?;
+    let mut shoe = Shoe::new(rules.packs);
+    seen.iter().for_each(|&rank| shoe.remove(rank));
+    let two_cards = hand.cards().len() == 2;
+    let options = Options {
+        double: two_cards,
+        splits: if hand.is_pair() { rules.max_resplits + 1 } else { 0 },
+        surrender: two_cards && rules.surrender != Surrender::None,
+    };
+    let values = strategy::expected_values_in(&hand, up, &shoe, deal, &rules, options);
+
+    let shown: Vec<String> = hand.cards().iter().map(|card| card.rank.to_string()).collect();
+    let drawn = match deal {
+        Deal::Finite => format!("{} packs drawn card by card", rules.packs),
+        Deal::Infinite => format!("{} packs at fixed odds", rules.packs),
+    };
+    let total = hand.total();
+    let soft = if total.soft { "soft " } else { "" };
+    println!(
+        "{} ({}{}) against {}, {}, {} other cards seen",
+        shown.join(" "),
+        soft,
+        total.value,
+        up.rank,
+        drawn,
+        seen.len()
+    );
+    let best = values.iter().map(|&(_, value)| value).fold(f64::NEG_INFINITY, f64::max);
+    for (action, value) in values {
+        let marker = if value == best { "  best" } else { "" };
+        println!("{:<10}{:>+9.4}{}", action.to_string(), value, marker);
+    }
+    Ok(())
+}
+
+/// Ranks written apart, or run together one character each.
+fn ranks(text: &str) -> Result<Vec<Rank>, String> {
+    let mut ranks = Vec::new();
+    for word in text.split(|c: char| c.is_whitespace() || c == ',').filter(|word| !word.is_empty())
+    {
+        match word.parse() {
+            Ok(rank) => ranks.push(rank),
+            Err(()) => {
+                for letter in word.chars() {
+                    let unknown = || format!("{} is not a card in {:?}", letter, text);
+                    ranks.push(letter.to_string().parse().map_err(|()| unknown())?);
+                }
+            }
+        }
+    }
+    Ok(ranks)
+}
+
+fn card(rank: Rank) -> Card {
+    Card { rank, suit: Suit::Spades }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blackjack_rust::card::Rank::*;
+
+    #[test]
+    fn ranks_read_run_together_apart_or_by_comma_in_any_case() {
+        assert_eq!(ranks("T6"), Ok(vec![Ten, Six]));
+        assert_eq!(ranks("10 6"), Ok(vec![Ten, Six]));
+        assert_eq!(ranks("k,6"), Ok(vec![King, Six]));
+        assert_eq!(ranks(" a5, 10 q "), Ok(vec![Ace, Five, Ten, Queen]));
+        assert_eq!(ranks(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn an_unknown_letter_is_named_with_what_it_was_found_in() {
+        assert_eq!(ranks("T6x"), Err("x is not a card in \"T6x\"".to_string()));
+        assert_eq!(ranks("106"), Err("1 is not a card in \"106\"".to_string()));
+    }
+}
@@ -0,0 +1,126 @@
+//! Plays the house rules headlessly and reports what they are worth.
+//!
+//! ```text
+//! blackjack-sim [--rounds <n>] [--seed <n>] [--policy basic|stand|dealer|random] [--threads <n>]
+//! ```
+//!
+//! The rules come from the same `BLACKJACK_*` variables the server reads, so
+//! the edge printed is the edge its tables deal. The same seed plays the same
+//! rounds, on every core the machine has unless told otherwise, and prints
+//! the same report whatever the number of threads.
+
+use blackjack_rust::config::Config;
+use blackjack_rust::policy::{self, Policy, NAMES};
+use blackjack_rust::rules::{HoleCard, Outcome, Rules, Surrender};
+use blackjack_rust::sim::{self, Report};
+use std::env;
+use std::error::Error;
+use std::thread;
+use std::time::Instant;
+
+/// Standard errors either side of the measured edge for a 95% interval.
+const Z_95: f64 = 1.96;
+
+struct Args {
+    rounds: u64,
+    seed: u64,
+    policy: String,
+    threads: usize,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let cores = thread::available_parallelism().map_or(1, usize::from);
+        let mut parsed =
+            Args { rounds: 1_000_000, seed: 1, policy: "basic".to_string(), threads: cores };
+        while let Some(flag) = args.next() {
+            let value = args.next().ok_or_else(|| format!("{} needs a value", flag))?;
+            let unparsable = || format!("{} is not a valid value for {}", value, flag);
+            match flag.as_str() {
+                "--rounds" => parsed.rounds = value.parse().map_err(|_| unparsable())?,
+                "--seed" => parsed.seed = value.parse().map_err(|_| unparsable())?,
+                "--policy" => parsed.policy = value,
+                "--threads" => parsed.threads = value.parse().map_err(|_| unparsable())?,
+                _ => return Err(format!("unknown option {}", flag)),
+            }
+        }
+        if parsed.rounds < 2 {
+            return Err("--rounds must be at least 2 to measure a spread".to_string());
+        }
+        if parsed.threads == 0 {
+            return Err("--threads must be at least 1".to_string());
+        }
+        Ok(parsed)
+    }
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("blackjack-sim: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse(env::args().skip(1))?;
+    let rules = Config::rules_from_env()?;
+    if policy::named(&args.policy).is_none() {
+        let known = NAMES.join(", ");
+        return Err(format!("no policy called {:?}, only {}", args.policy, known).into());
+    }
+    let started = Instant::now();
+    let fresh = || -> Box<dyn Policy> { policy::named(&args.policy).expect("checked above") };
+    let report = sim::run_sharded(rules, args.seed, args.rounds, args.threads, fresh)?;
+    println!("{}, {} policy, seed {}", describe(&rules), args.policy, args.seed);
+    let elapsed = started.elapsed().as_secs_f64();
+    let threads = if args.threads == 1 {
+        "1 thread".to_string()
+    } else {
+        format!("{} threads", args.threads)
+    };
+    println!("{} rounds in {:.1}s on {}", report.rounds, elapsed, threads);
+    print(&report);
+    Ok(())
+}
+
+fn describe(rules: &Rules) -> String {
+    let hole_card = match rules.hole_card {
+        HoleCard::Peek => "peek",
+        HoleCard::European => "european",
+    };
+    let surrender = match rules.surrender {
+        Surrender::None => "no",
+        Surrender::Late => "late",
+        Surrender::Early => "early",
+    };
+    format!(
+        "{} packs, {}, {} hole card, {} surrender, up to {} resplits, blackjack pays {}",
+        rules.packs,
+        if rules.dealer_hits_soft_17 { "H17" } else { "S17" },
+        hole_card,
+        surrender,
+        rules.max_resplits,
+        rules.natural_pays,
+    )
+}
+
+fn print(report: &Report) {
+    println!(
+        "house edge    {:.3}% ± {:.3}% (95% confidence)",
+        100.0 * report.house_edge(),
+        100.0 * report.margin(Z_95)
+    );
+    println!("sd per round  {:.3} wagers", report.sd_per_round());
+    println!("hands settled");
+    for outcome in Outcome::ALL {
+        let label = match outcome {
+            Outcome::Blackjack => "blackjack",
+            Outcome::EvenMoney => "even money",
+            Outcome::Win => "win",
+            Outcome::Push => "push",
+            Outcome::Surrender => "surrender",
+            Outcome::Lose => "lose",
+        };
+        println!("  {:<12}{:>7.3}%", label, 100.0 * report.frequency(outcome));
+    }
+}
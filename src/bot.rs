@@ -0,0 +1,182 @@
+//! The house's bots: players the server seats itself, so that a tester alone
+//! at a table that waits for company still gets a game.
+//!
+//! A bot is a seat like any other, played by a [`Policy`] through
+//! [`Table::apply`], so it wagers, draws and settles by exactly the rules a
+//! player does. [`Bots`] keeps up to its limit of them at a table for as long
+//! as a player is in the game there, and sends them all away once none is:
+//! left to themselves, bots would deal round after round to nobody. A player
+//! who wants the seat a bot is in gets it; the table itself makes the bot
+//! leave.
+
+use crate::policy::{self, BasicStrategy, Policy};
+use crate::table::{Command, Event, SeatId, Table, TableError, Wait, MAX_SEATS};
+use rand::Rng;
+
+/// What the bot numbered `number` sits down as: `Bot 1`, `Bot 2` and so on.
+pub fn name(number: usize) -> String {
+    format!("Bot {}", number)
+}
+
+/// Whether `name` is one a bot could sit down as, in any case. The ledger
+/// keeps these names from players, so a bot's chips are never anyone's.
+pub fn is_name(name: &str) -> bool {
+    let lowered = name.to_lowercase();
+    let number = lowered.strip_prefix("bot ").unwrap_or_default();
+    !number.is_empty() && number.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+/// The bots at one table.
+pub struct Bots {
+    limit: usize,
+    policies: Vec<String>,
+    seated: Vec<Bot>,
+    /// Bots sat down so far, so that each new one takes the next policy.
+    arrivals: usize,
+}
+
+struct Bot {
+    number: usize,
+    id: SeatId,
+    policy: Box<dyn Policy + Send>,
+}
+
+impl Bots {
+    /// Up to `limit` bots, each playing the next of `policies` as it sits
+    /// down. A name [`policy::named`] does not know plays basic strategy.
+    pub fn new(limit: usize, policies: &[String]) -> Self {
+        Bots { limit, policies: policies.to_vec(), seated: Vec::new(), arrivals: 0 }
+    }
+
+    /// Seats bots or sends them away as players come and go, then makes
+    /// every move the table is waiting on a bot for, until it is waiting on a
+    /// player again. A bot wagers what its policy says, declines insurance
+    /// and even money, and stands if its policy picks a move the table
+    /// refuses.
+    pub fn act<R: Rng>(&mut self, table: &mut Table<R>) -> Result<Vec<Event>, TableError> {
+        let mut events = Vec::new();
+        loop {
+            // A move can end the round, and a player's last chips with it.
+            self.seated.retain(|bot| table.is_seated(&bot.id));
+            if table.playing() == 0 {
+                for bot in self.seated.drain(..) {
+                    events.extend(table.leave(&bot.id)?);
+                }
+                return Ok(events);
+            }
+            while self.seated.len() < self.limit
+                && table.occupancy() + self.seated.len() < MAX_SEATS
+            {
+                events.extend(self.sit_down(table)?);
+            }
+            let Some((index, wait)) = self.next_to_act(table) else {
+                return Ok(events);
+            };
+            let rules = *table.rules();
+            let bot = &mut self.seated[index];
+            let command = match wait {
+                Wait::Bet => {
+                    let bankroll = table.bankroll(&bot.id).unwrap_or(0);
+                    Command::Bet(bot.policy.wager(&rules).min(bankroll))
+                }
+                Wait::Insurance => Command::Decline,
+                Wait::Decision { .. } => bot.policy.play(&table.turn(&bot.id)?, &rules).into(),
+            };
+            let moved = match table.apply(&bot.id, command) {
+                Err(_) if matches!(wait, Wait::Decision { .. }) => {
+                    table.apply(&bot.id, Command::Stand)
+                }
+                moved => moved,
+            };
+            events.extend(moved?);
+        }
+    }
+
+    /// Seats one more bot under the lowest number not in use.
+    fn sit_down<R: Rng>(&mut self, table: &mut Table<R>) -> Result<Vec<Event>, TableError> {
+        let taken = |number| self.seated.iter().any(|bot: &Bot| bot.number == number);
+        let number = (1..).find(|&number| !taken(number)).unwrap_or_default();
+        let id = format!("bot-{}", number);
+        let events = table.join_bot(&id, &name(number))?;
+        let chosen = self.policies.get(self.arrivals % self.policies.len().max(1));
+        let policy = chosen.and_then(|name| policy::named(name));
+        let policy = policy.unwrap_or_else(|| Box::<BasicStrategy>::default());
+        self.seated.push(Bot { number, id, policy });
+        self.arrivals += 1;
+        Ok(events)
+    }
+
+    /// The first bot the table is waiting on, and what for.
+    fn next_to_act<R: Rng>(&self, table: &Table<R>) -> Option<(usize, Wait)> {
+        self.seated
+            .iter()
+            .enumerate()
+            .find_map(|(index, bot)| Some((index, table.waiting_for(&bot.id)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::fixtures::house;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn bots(limit: usize) -> Bots {
+        Bots::new(limit, &["basic".to_string(), "random".to_string()])
+    }
+
+    #[test]
+    fn bots_sit_only_beside_a_player_and_leave_with_the_last_one() {
+        let mut table = Table::new(house(), StdRng::seed_from_u64(25));
+        let mut bots = bots(2);
+        assert_eq!(bots.act(&mut table), Ok(Vec::new()), "an empty table stays empty");
+
+        table.join("a", "Ada").expect("seat is free");
+        let events = bots.act(&mut table).expect("bots sit and bet");
+        let joined = |number| Event::Joined { name: name(number), bankroll: 200, bot: true };
+        assert_eq!(events[..2], [joined(1), joined(2)]);
+        assert_eq!((table.occupancy(), table.waiting_for("a")), (1, Some(Wait::Bet)));
+        assert!(events.iter().all(|event| event.name() != Some("Ada")));
+
+        table.leave("a").expect("seated");
+        // Ada leaving deals the bots' wagers, and they walk away from the hands.
+        let events = bots.act(&mut table).expect("bots leave");
+        let left: Vec<&Event> = events.iter().filter(|e| matches!(e, Event::Left { .. })).collect();
+        assert_eq!(left, [&Event::Left { name: name(1) }, &Event::Left { name: name(2) }]);
+        assert!(!table.is_seated("bot-1") && !table.is_seated("bot-2"));
+    }
+
+    #[test]
+    fn bots_play_whole_rounds_and_never_keep_a_player_waiting() {
+        let mut table = Table::new(house(), StdRng::seed_from_u64(25));
+        let mut bots = bots(3);
+        table.join_with_bankroll("a", "Ada", 3_000).expect("seat is free");
+        let (mut rounds, mut bot_settlements) = (0, 0);
+        while rounds < 60 {
+            let command = match table.waiting_for("a").expect("the table waits on Ada alone") {
+                Wait::Bet => Command::Bet(10),
+                Wait::Insurance => Command::Decline,
+                Wait::Decision { .. } => Command::Stand,
+            };
+            let mut events = table.apply("a", command).expect("Ada's move is accepted");
+            events.extend(bots.act(&mut table).expect("every bot move is accepted"));
+            for event in &events {
+                match event {
+                    Event::Settled { name, .. } if name == "Ada" => rounds += 1,
+                    Event::Settled { name, .. } if is_name(name) => bot_settlements += 1,
+                    _ => {}
+                }
+            }
+        }
+        assert!(bot_settlements >= 60, "only {} bot hands settled", bot_settlements);
+        assert_eq!(table.occupancy(), 1);
+    }
+
+    #[test]
+    fn a_bot_name_is_bot_and_a_number_in_any_case() {
+        assert_eq!(name(3), "Bot 3");
+        assert!(is_name("Bot 3") && is_name("bOT 12"));
+        assert!(!is_name("Bot") && !is_name("Bot ") && !is_name("Bot x") && !is_name("Botany 1"));
+    }
+}
@@ -2,6 +2,7 @@
 
 use rand::Rng;
 use std::fmt;
+use std::str::FromStr;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Suit {
@@ -92,6 +93,20 @@ impl fmt::Display for Rank {
     }
 }
 
+/// Reads back what [`Rank`]'s `Display` writes, in either case, and `T` for
+/// a ten as charts print it.
+impl FromStr for Rank {
+    type Err = ();
+
+    fn from_str(raw: &str) -> Result<Self, ()> {
+        let raw = raw.trim().to_uppercase();
+        if raw == "T" {
+            return Ok(Self::Ten);
+        }
+        Self::ALL.into_iter().find(|rank| rank.to_string() == raw).ok_or(())
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Card {
     pub rank: Rank,
@@ -118,6 +133,11 @@ impl Deck {
         Self(pack.repeat(packs as usize))
     }
 
+    /// A shoe holding exactly `cards`, in order. The last card is drawn first.
+    pub fn from_cards(cards: Vec<Card>) -> Self {
+        Self(cards)
+    }
+
     /// Fisher-Yates: card `i` swaps with a card drawn from `0..=i`, never from
     /// the whole shoe. Sampling the full range instead produces `n^n` equally
     /// likely swap sequences over `n!` permutations, and `n!` does not divide
@@ -128,6 +148,11 @@ impl Deck {
         }
     }
 
+    /// What is left, in dealing order from the back.
+    pub fn cards(&self) -> &[Card] {
+        &self.0
+    }
+
     pub fn draw(&mut self) -> Option<Card> {
         self.0.pop()
     }
@@ -176,6 +201,17 @@ mod tests {
         assert_eq!(Rank::Ten.points(), 10);
     }
 
+    #[test]
+    fn a_rank_reads_back_as_it_prints_and_a_ten_as_t() {
+        for rank in Rank::ALL {
+            assert_eq!(rank.to_string().to_lowercase().parse(), Ok(rank));
+        }
+        assert_eq!("T".parse(), Ok(Rank::Ten));
+        assert_eq!(" 10 ".parse(), Ok(Rank::Ten));
+        assert_eq!("1".parse::<Rank>(), Err(()));
+        assert_eq!("AK".parse::<Rank>(), Err(()));
+    }
+
     #[test]
     fn shuffle_permutes_without_adding_or_losing_a_card() {
         let ordered = Deck::new(2);
@@ -0,0 +1,85 @@
+//! Table chat: what a line of it is, and how often a seat may send one.
+//!
+//! Parsing and rendering live in [`crate::protocol`] with the rest of the wire
+//! format; the table task in `src/main.rs` relays each line to everyone at the
+//! table, in order with the game's own events.
+
+use crate::table::SeatId;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Longer lines are cut, not refused: a chat line is never worth an error.
+pub const MAX_CHAT_LEN: usize = 200;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Chat {
+    Say(String),
+    /// `/me waves` — said of the speaker rather than by them.
+    Emote(String),
+}
+
+/// Lines a seat may send in a burst before it is held to the steady rate.
+const BURST: u32 = 4;
+/// The steady rate, one line per interval. Every client's mailbox holds 64
+/// lines, so a full table of seats and spectators at this rate still leaves
+/// room for the game itself.
+const INTERVAL: Duration = Duration::from_secs(2);
+
+/// A per-seat rate limit, told the time like [`crate::table::Table::tick`]
+/// so that it is testable without sleeping. Each seat's next free slot moves
+/// on by one interval per line, and a line is refused while that slot is more
+/// than a burst ahead of now.
+#[derive(Debug, Default)]
+pub struct Throttle {
+    next: HashMap<SeatId, Instant>,
+}
+
+impl Throttle {
+    pub fn allow(&mut self, id: &str, now: Instant) -> bool {
+        let next = self.next.get(id).map_or(now, |&next| next.max(now));
+        if next > now + INTERVAL * (BURST - 1) {
+            return false;
+        }
+        self.next.insert(id.to_string(), next + INTERVAL);
+        true
+    }
+
+    /// Forgets seats that are no longer at the table.
+    pub fn retain(&mut self, mut present: impl FnMut(&str) -> bool) {
+        self.next.retain(|id, _| present(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_burst_is_allowed_and_then_the_steady_rate_applies() {
+        let mut throttle = Throttle::default();
+        let start = Instant::now();
+        assert!((0..BURST).all(|_| throttle.allow("a", start)));
+        assert!(!throttle.allow("a", start), "the burst is spent");
+        assert!(!throttle.allow("a", start + INTERVAL / 2));
+        assert!(throttle.allow("a", start + INTERVAL), "one more after an interval");
+        assert!(!throttle.allow("a", start + INTERVAL));
+    }
+
+    #[test]
+    fn each_seat_has_its_own_allowance() {
+        let mut throttle = Throttle::default();
+        let start = Instant::now();
+        (0..BURST).for_each(|_| assert!(throttle.allow("a", start)));
+        assert!(throttle.allow("b", start));
+    }
+
+    #[test]
+    fn a_quiet_seat_earns_back_its_whole_burst_but_no_more() {
+        let mut throttle = Throttle::default();
+        let start = Instant::now();
+        (0..BURST).for_each(|_| assert!(throttle.allow("a", start)));
+        let later = start + INTERVAL * 60;
+        assert!((0..BURST).all(|_| throttle.allow("a", later)));
+        assert!(!throttle.allow("a", later));
+    }
+}
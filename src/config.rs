@@ -1,16 +1,54 @@
 //! Startup configuration, read from the environment and validated eagerly.
 
-use crate::rules::Rules;
-use crate::table::MAX_SEATS;
+use crate::count::System;
+use crate::lobby::{TableSpec, MAX_TABLES};
+use crate::policy;
+use crate::rules::{HoleCard, Odds, Rules, Surrender};
+use crate::table::{Clocks, MAX_SEATS};
 use std::env;
 use std::error::Error;
 use std::fmt;
 use std::str::FromStr;
+use std::time::Duration;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Config {
     pub bind_addr: String,
+    /// Where browsers connect over WebSocket. Unset, there is no listener.
+    pub ws_addr: Option<String>,
+    /// An encrypted listener alongside the plain one. Unset, there is none.
+    pub tls: Option<Tls>,
+    /// The account journal. See [`crate::ledger`].
+    pub ledger_path: String,
+    /// How long a dropped player's seat is held for them to resume. 0 gives
+    /// it up at once.
+    pub reconnect_grace_secs: u64,
+    /// How long every table waits for a wager and for each decision.
+    pub clocks: Clocks,
+    /// The house rules, which every table plays by unless its spec says
+    /// otherwise.
     pub rules: Rules,
+    /// The tables open at startup. Players can open more from the lobby.
+    pub tables: Vec<TableSpec>,
+    /// The counting system every table quizzes its players on. Unset, the
+    /// tables do not train counting.
+    pub trainer: Option<System>,
+    /// How many of the house's bots may sit at each table while a player is
+    /// in the game there. 0 seats none.
+    pub bots: usize,
+    /// The policies the bots play, from [`policy::NAMES`], taken in turn as
+    /// they sit down.
+    pub bot_policies: Vec<String>,
+}
+
+/// Where the TLS listener binds, and the PEM files it presents. The files are
+/// read once at startup, so a bad path stops the server rather than the
+/// first player to connect.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tls {
+    pub addr: String,
+    pub cert_path: String,
+    pub key_path: String,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -46,25 +84,97 @@ impl Config {
     pub fn read(get: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
         let config = Config {
             bind_addr: required("BLACKJACK_BIND_ADDR", &get)?,
-            rules: Rules {
-                packs: parsed("BLACKJACK_PACKS", 6, &get)?,
-                dealer_hits_soft_17: parsed("BLACKJACK_DEALER_HITS_SOFT_17", false, &get)?,
-                min_bet: parsed("BLACKJACK_MIN_BET", 5, &get)?,
-                starting_bankroll: parsed("BLACKJACK_STARTING_BANKROLL", 200, &get)?,
-                min_players: parsed("BLACKJACK_MIN_PLAYERS", 1, &get)?,
+            ws_addr: optional("BLACKJACK_WS_ADDR", &get),
+            tls: tls(&get)?,
+            ledger_path: parsed("BLACKJACK_LEDGER_PATH", "blackjack.ledger".to_string(), &get)?,
+            reconnect_grace_secs: parsed("BLACKJACK_RECONNECT_GRACE_SECS", 60, &get)?,
+            clocks: Clocks {
+                betting: Duration::from_secs(parsed("BLACKJACK_BETTING_SECS", 30, &get)?),
+                decision: Duration::from_secs(parsed("BLACKJACK_DECISION_SECS", 30, &get)?),
             },
+            rules: Self::read_rules(&get)?,
+            tables: table_specs("BLACKJACK_TABLES", &get)?,
+            trainer: trainer("BLACKJACK_COUNT_TRAINER", &get)?,
+            bots: parsed("BLACKJACK_BOTS", 0, &get)?,
+            bot_policies: bot_policies("BLACKJACK_BOT_POLICIES", &get)?,
         };
         config.validated()
     }
 
-    fn validated(self) -> Result<Self, ConfigError> {
-        let Rules { packs, min_bet, starting_bankroll, min_players, .. } = self.rules;
+    /// The house rules alone, for a tool that plays by them without serving
+    /// anyone — the simulator. Validated like the rest, so a sim cannot run
+    /// on rules the server would refuse.
+    pub fn rules_from_env() -> Result<Rules, ConfigError> {
+        Self::read_rules(|var| env::var(var).ok())
+    }
+
+    pub fn read_rules(get: impl Fn(&str) -> Option<String>) -> Result<Rules, ConfigError> {
+        let rules = Rules {
+            packs: parsed("BLACKJACK_PACKS", 6, &get)?,
+            dealer_hits_soft_17: parsed("BLACKJACK_DEALER_HITS_SOFT_17", false, &get)?,
+            min_bet: parsed("BLACKJACK_MIN_BET", 5, &get)?,
+            max_bet: parsed("BLACKJACK_MAX_BET", 500, &get)?,
+            starting_bankroll: parsed("BLACKJACK_STARTING_BANKROLL", 200, &get)?,
+            min_players: parsed("BLACKJACK_MIN_PLAYERS", 1, &get)?,
+            max_rounds_sat_out: parsed("BLACKJACK_MAX_ROUNDS_SAT_OUT", 5, &get)?,
+            max_resplits: parsed("BLACKJACK_MAX_RESPLITS", 2, &get)?,
+            split_aces_one_card: parsed("BLACKJACK_SPLIT_ACES_ONE_CARD", true, &get)?,
+            split_ace_21_is_natural: parsed("BLACKJACK_SPLIT_ACE_21_IS_NATURAL", false, &get)?,
+            surrender: parsed("BLACKJACK_SURRENDER", Surrender::None, &get)?,
+            hole_card: parsed("BLACKJACK_HOLE_CARD", HoleCard::Peek, &get)?,
+            original_bets_only: parsed("BLACKJACK_ORIGINAL_BETS_ONLY", false, &get)?,
+            natural_pays: parsed("BLACKJACK_NATURAL_PAYS", Odds::THREE_TO_TWO, &get)?,
+        };
+        let Rules {
+            packs,
+            min_bet,
+            max_bet,
+            starting_bankroll,
+            min_players,
+            max_rounds_sat_out,
+            max_resplits,
+            natural_pays,
+            ..
+        } = rules;
         range("BLACKJACK_PACKS", "between 1 and 8", (1..=8).contains(&packs))?;
         range("BLACKJACK_MIN_BET", "at least 1", min_bet >= 1)?;
+        range("BLACKJACK_MAX_BET", "at least BLACKJACK_MIN_BET", max_bet >= min_bet)?;
         let affordable = starting_bankroll >= min_bet;
         range("BLACKJACK_STARTING_BANKROLL", "at least BLACKJACK_MIN_BET", affordable)?;
         let seats = (1..=MAX_SEATS).contains(&min_players);
         range("BLACKJACK_MIN_PLAYERS", "between 1 and 7", seats)?;
+        range("BLACKJACK_MAX_RESPLITS", "between 0 and 3", max_resplits <= 3)?;
+        // Every hand a maximum bet can split into, doubled and won, paid on
+        // top of the starting bankroll, must still count in a u32 of chips.
+        let hands = u64::from(max_resplits) + 1;
+        let most = u64::from(starting_bankroll) + 4 * hands * u64::from(max_bet);
+        let expected = "low enough that a maximum bet split, doubled and won on every hand \
+                        leaves BLACKJACK_STARTING_BANKROLL under 2^32 chips";
+        range("BLACKJACK_MAX_BET", expected, most <= u64::from(u32::MAX))?;
+        let patience = max_rounds_sat_out <= 100;
+        range("BLACKJACK_MAX_ROUNDS_SAT_OUT", "between 0 and 100", patience)?;
+        let ratio = natural_pays.win >= 1 && natural_pays.per >= 1;
+        range("BLACKJACK_NATURAL_PAYS", "a ratio like 3:2 of two whole numbers above 0", ratio)?;
+        let payable = natural_pays.pays_up_to(max_bet);
+        let expected = "a ratio that pays a natural on BLACKJACK_MAX_BET in under 2^32 chips";
+        range("BLACKJACK_NATURAL_PAYS", expected, payable)?;
+        Ok(rules)
+    }
+
+    fn validated(self) -> Result<Self, ConfigError> {
+        let grace = self.reconnect_grace_secs <= 600;
+        range("BLACKJACK_RECONNECT_GRACE_SECS", "between 0 and 600", grace)?;
+        let Clocks { betting, decision } = self.clocks;
+        range("BLACKJACK_BETTING_SECS", "between 0 and 600", betting.as_secs() <= 600)?;
+        range("BLACKJACK_DECISION_SECS", "between 0 and 600", decision.as_secs() <= 600)?;
+        let floor = (1..=MAX_TABLES).contains(&self.tables.len());
+        range("BLACKJACK_TABLES", "between 1 and 16 tables", floor)?;
+        let stakes = self.tables.iter().all(|spec| spec.apply(&self.rules).is_ok());
+        let expected =
+            "minimum bets no higher than BLACKJACK_MAX_BET or BLACKJACK_STARTING_BANKROLL";
+        range("BLACKJACK_TABLES", expected, stakes)?;
+        // A bot only ever sits beside a player, so one seat is always theirs.
+        range("BLACKJACK_BOTS", "between 0 and 6", self.bots < MAX_SEATS)?;
         Ok(self)
     }
 }
@@ -76,6 +186,10 @@ fn required(
     get(var).filter(|value| !value.trim().is_empty()).ok_or(ConfigError::Missing(var))
 }
 
+fn optional(var: &'static str, get: &impl Fn(&str) -> Option<String>) -> Option<String> {
+    get(var).filter(|value| !value.trim().is_empty())
+}
+
 fn parsed<T: FromStr>(
     var: &'static str,
     default: T,
@@ -87,6 +201,58 @@ fn parsed<T: FromStr>(
     raw.trim().parse().map_err(|_| ConfigError::Unparsable { var, value: raw })
 }
 
+/// All three of the TLS variables or none of them: a certificate with nowhere
+/// to serve it, or an address with no certificate, is a mistake worth naming.
+fn tls(get: &impl Fn(&str) -> Option<String>) -> Result<Option<Tls>, ConfigError> {
+    let vars = ["BLACKJACK_TLS_ADDR", "BLACKJACK_TLS_CERT", "BLACKJACK_TLS_KEY"];
+    if vars.iter().all(|var| optional(var, get).is_none()) {
+        return Ok(None);
+    }
+    Ok(Some(Tls {
+        addr: required("BLACKJACK_TLS_ADDR", get)?,
+        cert_path: required("BLACKJACK_TLS_CERT", get)?,
+        key_path: required("BLACKJACK_TLS_KEY", get)?,
+    }))
+}
+
+/// A comma-separated list of [`TableSpec`]s, one table each. Unset, the floor
+/// opens with a single table on the house rules.
+fn table_specs(
+    var: &'static str,
+    get: &impl Fn(&str) -> Option<String>,
+) -> Result<Vec<TableSpec>, ConfigError> {
+    let Some(raw) = get(var).filter(|value| !value.trim().is_empty()) else {
+        return Ok(vec![TableSpec::default()]);
+    };
+    let specs: Result<Vec<TableSpec>, ()> = raw.split(',').map(str::parse).collect();
+    specs.map_err(|_| ConfigError::Unparsable { var, value: raw })
+}
+
+fn trainer(
+    var: &'static str,
+    get: &impl Fn(&str) -> Option<String>,
+) -> Result<Option<System>, ConfigError> {
+    let Some(raw) = optional(var, get) else {
+        return Ok(None);
+    };
+    raw.parse().map(Some).map_err(|_| ConfigError::Unparsable { var, value: raw })
+}
+
+/// A comma-separated list of policy names. Unset, bots play basic strategy.
+fn bot_policies(
+    var: &'static str,
+    get: &impl Fn(&str) -> Option<String>,
+) -> Result<Vec<String>, ConfigError> {
+    let Some(raw) = optional(var, get) else {
+        return Ok(vec!["basic".to_string()]);
+    };
+    let names: Vec<String> = raw.split(',').map(|name| name.trim().to_lowercase()).collect();
+    if !names.iter().all(|name| policy::NAMES.contains(&name.as_str())) {
+        return Err(ConfigError::Unparsable { var, value: raw });
+    }
+    Ok(names)
+}
+
 fn range(var: &'static str, expected: &str, ok: bool) -> Result<(), ConfigError> {
     if ok {
         return Ok(());
@@ -132,11 +298,72 @@ mod tests {
     fn documented_defaults_apply_when_only_the_address_is_set() {
         let config = Config::read(source(&valid())).expect("defaults are valid");
         assert_eq!(config.bind_addr, "127.0.0.1:8080");
+        assert_eq!(config.ledger_path, "blackjack.ledger");
         assert_eq!(config.rules.packs, 6);
         assert_eq!(config.rules.min_bet, 5);
         assert!(!config.rules.dealer_hits_soft_17);
     }
 
+    #[test]
+    fn the_websocket_listener_is_off_unless_given_an_address() {
+        assert_eq!(Config::read(source(&valid())).expect("valid").ws_addr, None);
+        assert_eq!(with(("BLACKJACK_WS_ADDR", " ")).expect("valid").ws_addr, None);
+        let on = with(("BLACKJACK_WS_ADDR", "127.0.0.1:8081")).expect("valid");
+        assert_eq!(on.ws_addr.as_deref(), Some("127.0.0.1:8081"));
+    }
+
+    #[test]
+    fn the_rules_alone_need_no_address_and_are_held_to_the_same_ranges() {
+        assert_eq!(
+            Config::read_rules(source(&[])),
+            Ok(Config::read(source(&valid())).unwrap().rules)
+        );
+        let error = Config::read_rules(source(&[("BLACKJACK_PACKS", "9")])).unwrap_err();
+        assert!(matches!(error, ConfigError::OutOfRange { var: "BLACKJACK_PACKS", .. }));
+    }
+
+    #[test]
+    fn the_count_trainer_is_off_unless_given_a_known_system() {
+        assert_eq!(Config::read(source(&valid())).expect("valid").trainer, None);
+        let on = with(("BLACKJACK_COUNT_TRAINER", "Hi-Lo")).expect("valid");
+        assert_eq!(on.trainer, Some(crate::count::HI_LO));
+        let error = with(("BLACKJACK_COUNT_TRAINER", "red-7")).unwrap_err();
+        let value = "red-7".to_string();
+        assert_eq!(error, ConfigError::Unparsable { var: "BLACKJACK_COUNT_TRAINER", value });
+    }
+
+    #[test]
+    fn bots_are_off_unless_asked_for_and_play_only_known_policies() {
+        let config = Config::read(source(&valid())).expect("valid");
+        assert_eq!((config.bots, config.bot_policies), (0, vec!["basic".to_string()]));
+        let mut pairs = valid();
+        pairs.push(("BLACKJACK_BOTS", "3"));
+        pairs.push(("BLACKJACK_BOT_POLICIES", "Basic, random"));
+        let config = Config::read(source(&pairs)).expect("valid");
+        assert_eq!((config.bots, config.bot_policies), (3, vec!["basic".into(), "random".into()]));
+        let error = with(("BLACKJACK_BOTS", "7")).unwrap_err();
+        let expected = "between 0 and 6".to_string();
+        assert_eq!(error, ConfigError::OutOfRange { var: "BLACKJACK_BOTS", expected });
+        let error = with(("BLACKJACK_BOT_POLICIES", "basic,card-counter")).unwrap_err();
+        let value = "basic,card-counter".to_string();
+        assert_eq!(error, ConfigError::Unparsable { var: "BLACKJACK_BOT_POLICIES", value });
+    }
+
+    #[test]
+    fn tls_needs_an_address_a_certificate_and_a_key_or_none_of_them() {
+        assert_eq!(Config::read(source(&valid())).expect("valid").tls, None);
+        let error = with(("BLACKJACK_TLS_CERT", "cert.pem")).unwrap_err();
+        assert_eq!(error, ConfigError::Missing("BLACKJACK_TLS_ADDR"));
+        let mut pairs = valid();
+        pairs.push(("BLACKJACK_TLS_ADDR", "0.0.0.0:8443"));
+        pairs.push(("BLACKJACK_TLS_CERT", "cert.pem"));
+        let error = Config::read(source(&pairs)).unwrap_err();
+        assert_eq!(error, ConfigError::Missing("BLACKJACK_TLS_KEY"));
+        pairs.push(("BLACKJACK_TLS_KEY", "key.pem"));
+        let tls = Config::read(source(&pairs)).expect("valid").tls.expect("a TLS listener");
+        assert_eq!((tls.addr.as_str(), tls.key_path.as_str()), ("0.0.0.0:8443", "key.pem"));
+    }
+
     #[test]
     fn a_non_numeric_override_fails_loudly_instead_of_falling_back() {
         let error = with(("BLACKJACK_MIN_BET", "five")).unwrap_err();
@@ -162,12 +389,133 @@ mod tests {
         assert!(with(("BLACKJACK_PACKS", "8")).is_ok());
     }
 
+    #[test]
+    fn a_maximum_below_the_minimum_bet_is_rejected() {
+        let error = with(("BLACKJACK_MAX_BET", "4")).unwrap_err();
+        assert_eq!(error.to_string(), "BLACKJACK_MAX_BET must be at least BLACKJACK_MIN_BET");
+        assert_eq!(with(("BLACKJACK_MAX_BET", "5")).expect("a fixed-limit table").rules.max_bet, 5);
+    }
+
+    #[test]
+    fn a_maximum_bet_won_on_every_split_and_double_has_to_fit_in_the_bankroll() {
+        let mut pairs = valid();
+        pairs.extend([("BLACKJACK_MAX_RESPLITS", "0"), ("BLACKJACK_MAX_BET", "1000")]);
+        let at = |bankroll| {
+            let mut pairs = pairs.clone();
+            pairs.push(("BLACKJACK_STARTING_BANKROLL", bankroll));
+            Config::read(source(&pairs))
+        };
+        assert!(at("4294963295").is_ok(), "4,000 chips short of 2^32 exactly covers a double");
+        let error = at("4294963296").unwrap_err();
+        assert!(matches!(error, ConfigError::OutOfRange { var: "BLACKJACK_MAX_BET", .. }));
+        let mut pairs = valid();
+        pairs.extend([
+            ("BLACKJACK_MAX_BET", "1500000000"),
+            ("BLACKJACK_STARTING_BANKROLL", "4000000000"),
+        ]);
+        assert!(Config::read(source(&pairs)).is_err());
+    }
+
     #[test]
     fn a_bankroll_that_cannot_cover_the_minimum_bet_is_rejected() {
         let error = with(("BLACKJACK_STARTING_BANKROLL", "1")).unwrap_err();
         assert!(error.to_string().contains("BLACKJACK_MIN_BET"));
     }
 
+    #[test]
+    fn surrender_is_off_unless_a_variant_is_named() {
+        assert_eq!(Config::read(source(&valid())).expect("valid").rules.surrender, Surrender::None);
+        assert_eq!(
+            with(("BLACKJACK_SURRENDER", "late")).expect("valid").rules.surrender,
+            Surrender::Late
+        );
+        assert!(with(("BLACKJACK_SURRENDER", "yes")).is_err());
+    }
+
+    #[test]
+    fn the_hole_card_rule_defaults_to_the_american_peek() {
+        assert_eq!(Config::read(source(&valid())).expect("valid").rules.hole_card, HoleCard::Peek);
+        let european = with(("BLACKJACK_HOLE_CARD", "european")).expect("valid");
+        assert_eq!(european.rules.hole_card, HoleCard::European);
+        assert!(with(("BLACKJACK_HOLE_CARD", "none")).is_err());
+    }
+
+    #[test]
+    fn the_natural_payout_reads_as_a_ratio_and_defaults_to_three_to_two() {
+        let defaults = Config::read(source(&valid())).expect("valid");
+        assert_eq!(defaults.rules.natural_pays, Odds::THREE_TO_TWO);
+        let training = with(("BLACKJACK_NATURAL_PAYS", "6:5")).expect("valid");
+        assert_eq!(training.rules.natural_pays, Odds { win: 6, per: 5 });
+        assert!(matches!(
+            with(("BLACKJACK_NATURAL_PAYS", "1.5")),
+            Err(ConfigError::Unparsable { var: "BLACKJACK_NATURAL_PAYS", .. })
+        ));
+        assert!(matches!(
+            with(("BLACKJACK_NATURAL_PAYS", "3:0")),
+            Err(ConfigError::OutOfRange { var: "BLACKJACK_NATURAL_PAYS", .. })
+        ));
+        assert!(matches!(
+            with(("BLACKJACK_NATURAL_PAYS", "4294967295:1")),
+            Err(ConfigError::OutOfRange { var: "BLACKJACK_NATURAL_PAYS", .. })
+        ));
+    }
+
+    #[test]
+    fn one_house_table_opens_unless_the_tables_are_listed() {
+        let defaults = Config::read(source(&valid())).expect("valid");
+        assert_eq!(defaults.tables, vec![TableSpec::default()]);
+        let floor = with(("BLACKJACK_TABLES", "5/s17, 25/h17")).expect("valid");
+        let high = TableSpec { min_bet: Some(25), dealer_hits_soft_17: Some(true) };
+        assert_eq!(floor.tables[1], high);
+        assert_eq!(floor.tables.len(), 2);
+    }
+
+    #[test]
+    fn a_table_listing_that_cannot_be_opened_is_rejected() {
+        assert!(matches!(
+            with(("BLACKJACK_TABLES", "25/x17")),
+            Err(ConfigError::Unparsable { var: "BLACKJACK_TABLES", .. })
+        ));
+        assert!(matches!(
+            with(("BLACKJACK_TABLES", "5,1000")),
+            Err(ConfigError::OutOfRange { var: "BLACKJACK_TABLES", .. })
+        ));
+    }
+
+    #[test]
+    fn a_dropped_seat_is_held_for_at_most_ten_minutes() {
+        assert_eq!(Config::read(source(&valid())).expect("valid").reconnect_grace_secs, 60);
+        assert_eq!(
+            with(("BLACKJACK_RECONNECT_GRACE_SECS", "0")).expect("valid").reconnect_grace_secs,
+            0
+        );
+        assert!(with(("BLACKJACK_RECONNECT_GRACE_SECS", "601")).is_err());
+    }
+
+    #[test]
+    fn the_table_clocks_default_to_thirty_seconds_and_zero_turns_them_off() {
+        let clocks = Config::read(source(&valid())).expect("valid").clocks;
+        assert_eq!(clocks.betting, Duration::from_secs(30));
+        assert_eq!(clocks.decision, Duration::from_secs(30));
+        let off = with(("BLACKJACK_DECISION_SECS", "0")).expect("valid").clocks;
+        assert_eq!(off.decision, Duration::ZERO);
+        assert!(with(("BLACKJACK_BETTING_SECS", "601")).is_err());
+    }
+
+    #[test]
+    fn a_seat_may_sit_out_at_most_a_hundred_rounds_in_a_row() {
+        let rules = Config::read(source(&valid())).expect("valid").rules;
+        assert_eq!(rules.max_rounds_sat_out, 5);
+        assert!(with(("BLACKJACK_MAX_ROUNDS_SAT_OUT", "0")).is_ok());
+        assert!(with(("BLACKJACK_MAX_ROUNDS_SAT_OUT", "101")).is_err());
+    }
+
+    #[test]
+    fn resplits_are_capped_at_four_hands_a_seat() {
+        assert!(with(("BLACKJACK_MAX_RESPLITS", "4")).is_err());
+        assert_eq!(with(("BLACKJACK_MAX_RESPLITS", "0")).expect("valid").rules.max_resplits, 0);
+    }
+
     #[test]
     fn min_players_cannot_exceed_the_seat_count() {
         assert!(with(("BLACKJACK_MIN_PLAYERS", "8")).is_err());
@@ -0,0 +1,290 @@
+//! Card counting: the tag tables of the common systems, and a count kept from
+//! the table's own event stream.
+//!
+//! A [`Count`] sees only what a player at the table sees — the cards in
+//! [`Event::Dealt`], [`Event::DealerShows`] and [`Event::DealerHand`] — so it
+//! is the count a player could keep, not a peek into the [`crate::card::Deck`].
+//! The table's trainer keeps one to check answers against, and a bot can keep
+//! its own from the same lines.
+
+use crate::card::Rank;
+use crate::table::Event;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const PACK: i32 = 52;
+
+/// A counting system: one tag per rank, in the order of [`Rank::ALL`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct System {
+    pub name: &'static str,
+    tags: [i8; 13],
+}
+
+#[rustfmt::skip]
+pub const SYSTEMS: [System; 5] = [
+    //                                2   3   4   5   6   7   8   9   T   J   Q   K   A
+    System { name: "hi-lo",    tags: [ 1,  1,  1,  1,  1,  0,  0,  0, -1, -1, -1, -1, -1] },
+    System { name: "ko",       tags: [ 1,  1,  1,  1,  1,  1,  0,  0, -1, -1, -1, -1, -1] },
+    System { name: "hi-opt-i", tags: [ 0,  1,  1,  1,  1,  0,  0,  0, -1, -1, -1, -1,  0] },
+    System { name: "omega-ii", tags: [ 1,  1,  2,  2,  2,  1,  0, -1, -2, -2, -2, -2,  0] },
+    System { name: "zen",      tags: [ 1,  1,  2,  2,  2,  1,  0,  0, -2, -2, -2, -2, -1] },
+];
+
+pub const HI_LO: System = SYSTEMS[0];
+pub const KO: System = SYSTEMS[1];
+pub const HI_OPT_I: System = SYSTEMS[2];
+pub const OMEGA_II: System = SYSTEMS[3];
+pub const ZEN: System = SYSTEMS[4];
+
+impl System {
+    pub fn tag(&self, rank: Rank) -> i32 {
+        i32::from(self.tags[rank as usize])
+    }
+
+    /// What a whole pack counts to. Zero for a balanced system; KO counts to
+    /// +4, which is what lets it be played without dividing by packs left.
+    pub fn imbalance(&self) -> i32 {
+        Rank::ALL.iter().map(|&rank| 4 * self.tag(rank)).sum()
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.imbalance() == 0
+    }
+
+    /// Where the running count starts on a fresh shoe. An unbalanced system
+    /// starts below zero by one pack's imbalance for every pack after the
+    /// first, so that a neutral shoe crosses zero near its end — KO's usual
+    /// 4 - 4 x packs.
+    pub fn initial_count(&self, packs: u8) -> i32 {
+        self.imbalance() * (1 - i32::from(packs))
+    }
+}
+
+impl FromStr for System {
+    type Err = ();
+
+    fn from_str(raw: &str) -> Result<Self, ()> {
+        let lowered = raw.trim().to_lowercase();
+        SYSTEMS.into_iter().find(|system| system.name == lowered).ok_or(())
+    }
+}
+
+/// A running count over one shoe, kept from events.
+#[derive(Clone, Debug)]
+pub struct Count {
+    system: System,
+    packs: u8,
+    running: i32,
+    seen: u32,
+    /// Cards already counted in each player's hand this round. A `Dealt`
+    /// event repeats the whole hand, so only what is past this is new.
+    hands: HashMap<(String, usize), usize>,
+    dealer: usize,
+}
+
+impl Count {
+    pub fn new(system: System, packs: u8) -> Self {
+        Count {
+            system,
+            packs,
+            running: system.initial_count(packs),
+            seen: 0,
+            hands: HashMap::new(),
+            dealer: 0,
+        }
+    }
+
+    pub fn system(&self) -> System {
+        self.system
+    }
+
+    pub fn running(&self) -> i32 {
+        self.running
+    }
+
+    /// Packs not yet seen, by the cards that have been. A hole card nobody
+    /// was shown still counts as in the shoe, as it would for a player.
+    pub fn packs_left(&self) -> f64 {
+        let left = i32::from(self.packs) * PACK - self.seen as i32;
+        f64::from(left.max(1)) / f64::from(PACK)
+    }
+
+    /// The running count per pack left. An unbalanced system is played from
+    /// the running count alone, so it has none.
+    pub fn true_count(&self) -> Option<f64> {
+        self.system.is_balanced().then(|| f64::from(self.running) / self.packs_left())
+    }
+
+    pub fn reading(&self) -> Reading {
+        Reading { running: self.running, true_count: self.true_count() }
+    }
+
+    pub fn observe(&mut self, event: &Event) {
+        match event {
+            Event::Shuffled => *self = Count::new(self.system, self.packs),
+            Event::BettingOpened { .. } => {
+                self.hands.clear();
+                self.dealer = 0;
+            }
+            Event::Dealt { name, hand, cards, .. } => {
+                let counted = self.hands.insert((name.clone(), *hand), cards.len()).unwrap_or(0);
+                cards.iter().skip(counted).for_each(|card| self.add(card.rank));
+            }
+            Event::Split { name, hand, .. } => self.split(name, *hand),
+            Event::DealerShows { card, .. } => {
+                self.add(card.rank);
+                self.dealer = 1;
+            }
+            Event::DealerHand { cards, .. } => {
+                cards.iter().skip(self.dealer).for_each(|card| self.add(card.rank));
+                self.dealer = cards.len();
+            }
+            _ => {}
+        }
+    }
+
+    fn add(&mut self, rank: Rank) {
+        self.running += self.system.tag(rank);
+        self.seen += 1;
+    }
+
+    /// Splitting leaves one counted card in each half and pushes every later
+    /// hand of the seat one place to the right.
+    fn split(&mut self, name: &str, hand: usize) {
+        let mut later: Vec<usize> = self
+            .hands
+            .keys()
+            .filter(|(owner, index)| owner == name && *index > hand)
+            .map(|&(_, index)| index)
+            .collect();
+        later.sort_unstable_by(|a, b| b.cmp(a));
+        for index in later {
+            if let Some(counted) = self.hands.remove(&(name.to_string(), index)) {
+                self.hands.insert((name.to_string(), index + 1), counted);
+            }
+        }
+        self.hands.insert((name.to_string(), hand), 1);
+        self.hands.insert((name.to_string(), hand + 1), 1);
+    }
+}
+
+/// The count at the moment a player was asked for it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reading {
+    pub running: i32,
+    /// `None` for an unbalanced system, which is not asked for one.
+    pub true_count: Option<f64>,
+}
+
+impl Reading {
+    /// Checks an answer. The running count must be exact. A true count is
+    /// right if it is the exact one rounded either way, since players are
+    /// taught both to floor it and to round it; a missing one is wrong.
+    pub fn check(&self, running: i32, true_count: Option<i32>) -> (bool, Option<bool>) {
+        let close =
+            |exact: f64| true_count.is_some_and(|given| (f64::from(given) - exact).abs() < 1.0);
+        (running == self.running, self.true_count.map(close))
+    }
+}
+
+/// How a player has done on count checks so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    pub asked: u32,
+    pub running_right: u32,
+    pub true_right: u32,
+}
+
+/// One answer, marked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Verdict {
+    pub reading: Reading,
+    pub running: i32,
+    pub true_count: Option<i32>,
+    pub running_right: bool,
+    /// `None` when the system has no true count.
+    pub true_right: Option<bool>,
+    /// Including this answer.
+    pub score: Score,
+}
+
+impl Score {
+    /// Marks `running` and `true_count` against `reading`.
+    pub fn mark(&mut self, reading: Reading, running: i32, true_count: Option<i32>) -> Verdict {
+        let (running_right, true_right) = reading.check(running, true_count);
+        self.asked += 1;
+        self.running_right += u32::from(running_right);
+        self.true_right += u32::from(true_right == Some(true));
+        Verdict { reading, running, true_count, running_right, true_right, score: *self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Rank::*;
+    use crate::hand::fixtures::card;
+    use crate::hand::Total;
+
+    fn dealt(name: &str, hand: usize, ranks: &[Rank]) -> Event {
+        let cards = ranks.iter().map(|&rank| card(rank)).collect();
+        Event::Dealt { name: name.into(), hand, cards, total: Total { value: 0, soft: false } }
+    }
+
+    #[test]
+    fn every_balanced_system_counts_a_pack_to_zero_and_ko_to_four() {
+        for system in SYSTEMS {
+            assert_eq!(system.is_balanced(), system != KO, "{}", system.name);
+        }
+        assert_eq!(KO.imbalance(), 4);
+        assert_eq!(KO.initial_count(6), -20);
+        assert_eq!(HI_LO.initial_count(6), 0);
+        assert_eq!("Omega-II".parse(), Ok(OMEGA_II));
+        assert_eq!("wonging".parse::<System>(), Err(()));
+    }
+
+    #[test]
+    fn a_repeated_hand_is_counted_once_and_a_split_keeps_its_place() {
+        let mut count = Count::new(HI_LO, 1);
+        count.observe(&dealt("Ada", 0, &[Five, Five]));
+        count.observe(&Event::DealerShows { card: card(King), hole_card: true });
+        assert_eq!(count.running(), 1);
+        count.observe(&Event::Split { name: "Ada".into(), hand: 0, stake: 10 });
+        count.observe(&dealt("Ada", 0, &[Five, Two]));
+        count.observe(&dealt("Ada", 1, &[Five, Three]));
+        count.observe(&dealt("Ada", 0, &[Five, Two, Ace]));
+        assert_eq!(count.running(), 2);
+        let dealer = [King, Seven, Nine].map(card).to_vec();
+        count
+            .observe(&Event::DealerHand { cards: dealer, total: Total { value: 26, soft: false } });
+        assert_eq!(count.running(), 2, "the seven and nine are neutral");
+        assert_eq!(count.packs_left(), 44.0 / 52.0);
+    }
+
+    #[test]
+    fn the_true_count_divides_by_packs_left_and_a_shuffle_starts_over() {
+        let mut count = Count::new(HI_LO, 2);
+        count.observe(&dealt("Ada", 0, &[Two, Three, Four, Five]));
+        assert_eq!(count.true_count(), Some(4.0 / (100.0 / 52.0)));
+        count.observe(&Event::Shuffled);
+        assert_eq!((count.running(), count.true_count()), (0, Some(0.0)));
+        assert_eq!(Count::new(KO, 2).true_count(), None);
+    }
+
+    #[test]
+    fn a_true_count_rounded_either_way_is_right_and_the_running_count_must_be_exact() {
+        let reading = Reading { running: 7, true_count: Some(2.6) };
+        assert_eq!(reading.check(7, Some(2)), (true, Some(true)));
+        assert_eq!(reading.check(7, Some(3)), (true, Some(true)));
+        assert_eq!(reading.check(6, Some(4)), (false, Some(false)));
+        assert_eq!(reading.check(7, None), (true, Some(false)));
+        let ko = Reading { running: -3, true_count: None };
+        assert_eq!(ko.check(-3, Some(9)), (true, None));
+
+        let mut score = Score::default();
+        score.mark(reading, 7, Some(3));
+        let verdict = score.mark(reading, 6, Some(3));
+        assert_eq!(verdict.score, Score { asked: 2, running_right: 1, true_right: 2 });
+    }
+}
@@ -13,11 +13,14 @@ pub struct Total {
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Hand {
     cards: Vec<Card>,
+    /// Set on both halves of a split pair unless the table lets them keep a
+    /// natural: 21 on two cards after a split is normally an ordinary 21.
+    natural_barred: bool,
 }
 
 impl Hand {
     pub fn from_cards(cards: Vec<Card>) -> Self {
-        Self { cards }
+        Self { cards, natural_barred: false }
     }
 
     pub fn push(&mut self, card: Card) {
@@ -26,6 +29,15 @@ impl Hand {
 
     pub fn clear(&mut self) {
         self.cards.clear();
+        self.natural_barred = false;
+    }
+
+    /// Moves the second card of a pair into a hand of its own and returns it.
+    /// Unless `keeps_naturals`, neither half can be a blackjack afterwards.
+    pub fn split(&mut self, keeps_naturals: bool) -> Hand {
+        let second = self.cards.split_off(1);
+        self.natural_barred = !keeps_naturals;
+        Hand { cards: second, natural_barred: !keeps_naturals }
     }
 
     pub fn cards(&self) -> &[Card] {
@@ -47,9 +59,16 @@ impl Hand {
     }
 
     /// A natural: 21 on the opening two cards. Three cards totalling 21 are
-    /// not a blackjack and are not paid at 3:2.
+    /// not a blackjack and are not paid at 3:2, and neither, by default, is
+    /// 21 on two cards of a split hand.
     pub fn is_blackjack(&self) -> bool {
-        self.cards.len() == 2 && self.total().value == 21
+        !self.natural_barred && self.cards.len() == 2 && self.total().value == 21
+    }
+
+    /// Two cards of equal value. Any two ten-point cards make a pair, so a
+    /// king and a jack can be split like two kings.
+    pub fn is_pair(&self) -> bool {
+        matches!(self.cards.as_slice(), [a, b] if a.rank.points() == b.rank.points())
     }
 
     pub fn is_bust(&self) -> bool {
@@ -68,14 +87,17 @@ pub(crate) mod fixtures {
 
     /// Builds a hand from ranks; suits are irrelevant to blackjack scoring.
     pub fn hand(ranks: &[Rank]) -> Hand {
-        let cards = ranks.iter().map(|&rank| Card { rank, suit: Suit::Spades }).collect();
-        Hand::from_cards(cards)
+        Hand::from_cards(ranks.iter().map(|&rank| card(rank)).collect())
+    }
+
+    pub fn card(rank: Rank) -> Card {
+        Card { rank, suit: Suit::Spades }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::fixtures::hand;
+    use super::fixtures::{card, hand};
     use crate::card::Rank::*;
 
     #[test]
@@ -146,6 +168,29 @@ mod tests {
         assert!(!three.is_blackjack());
     }
 
+    #[test]
+    fn twenty_one_on_a_split_hand_is_not_a_natural_unless_it_keeps_naturals() {
+        let mut aces = hand(&[Ace, Ace]);
+        let mut other = aces.split(false);
+        aces.push(card(King));
+        other.push(card(Queen));
+        assert_eq!(aces.total().value, 21);
+        assert!(!aces.is_blackjack() && !other.is_blackjack());
+
+        let mut kept = hand(&[Ace, Ace]);
+        kept.split(true);
+        kept.push(card(King));
+        assert!(kept.is_blackjack());
+    }
+
+    #[test]
+    fn equal_ten_point_cards_are_a_pair_but_three_cards_are_not() {
+        assert!(hand(&[Eight, Eight]).is_pair());
+        assert!(hand(&[King, Jack]).is_pair());
+        assert!(!hand(&[Ace, King]).is_pair());
+        assert!(!hand(&[Four, Four, Four]).is_pair());
+    }
+
     #[test]
     fn twenty_one_is_not_a_bust() {
         assert!(!hand(&[Ace, King]).is_bust());
@@ -0,0 +1,356 @@
+//! Player accounts that outlive a connection, kept in an append-only journal.
+//!
+//! Every journal line is the whole state of one account — chips in hand and
+//! chips on the felt — so replay is "the last line for each name wins" and no
+//! line depends on another. A kill mid-write leaves a last line with no
+//! newline, which replay skips, and the line before it is still a complete
+//! account. Chips on the felt when the server died belong to a round that was
+//! never settled, so replay hands them back: a crash mid-round neither creates
+//! nor destroys a chip.
+//!
+//! The house's bots play with chips nobody owns, so nothing they do is kept,
+//! and no player may take one of their names.
+
+use crate::bot;
+use crate::rules::Rules;
+use crate::table::Event;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Account {
+    /// Chips the player holds.
+    pub bankroll: u32,
+    /// Chips wagered in the round being played and not yet settled.
+    pub staked: u32,
+}
+
+impl Account {
+    fn stake(&mut self, chips: u32) {
+        self.bankroll = self.bankroll.saturating_sub(chips);
+        self.staked += chips;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedgerError {
+    InUse,
+    /// The name is one the house's bots sit down under.
+    Reserved,
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InUse => write!(f, "that name is already playing"),
+            Self::Reserved => write!(f, "that name is kept for the house's bots"),
+        }
+    }
+}
+
+impl Error for LedgerError {}
+
+#[derive(Debug, Default)]
+pub struct Ledger {
+    accounts: HashMap<String, Account>,
+    in_use: HashSet<String>,
+}
+
+impl Ledger {
+    /// Rebuilds the accounts from a journal, refunding any unsettled stake.
+    pub fn replay(journal: &str) -> Self {
+        let mut ledger = Ledger::default();
+        let complete = journal.split_inclusive('\n').filter(|line| line.ends_with('\n'));
+        ledger.accounts.extend(complete.filter_map(parse_line));
+        ledger.accounts.values_mut().for_each(|account| {
+            account.bankroll += account.staked;
+            account.staked = 0;
+        });
+        ledger
+    }
+
+    pub fn account(&self, name: &str) -> Option<Account> {
+        self.accounts.get(name).copied()
+    }
+
+    /// Claims an account for one connection, since sitting at two tables at
+    /// once would spend the same chips twice. A name that has never played is
+    /// bought in at the starting bankroll; any other comes back with exactly
+    /// what it has, even too little to bet, which the table then refuses.
+    pub fn check_out(&mut self, name: &str, house: &Rules) -> Result<u32, LedgerError> {
+        if bot::is_name(name) {
+            return Err(LedgerError::Reserved);
+        }
+        if !self.in_use.insert(name.to_string()) {
+            return Err(LedgerError::InUse);
+        }
+        Ok(self.account(name).map_or(house.starting_bankroll, |account| account.bankroll))
+    }
+
+    /// Gives back an account that never reached a table. One that did is
+    /// given back by the table's own `Left`, `OutOfChips`, `SeatReleased` or
+    /// `StoppedWatching`, so it cannot be claimed again before its last round
+    /// is written down.
+    pub fn check_in(&mut self, name: &str) {
+        self.in_use.remove(name);
+    }
+
+    /// Applies one batch of a table's events and returns the journal lines
+    /// recording it: one per account touched, holding its state after the
+    /// last of them, so a seat's split hands settle in a single line.
+    pub fn record(&mut self, events: &[Event]) -> String {
+        let mut touched: Vec<&str> = Vec::new();
+        for name in events.iter().filter_map(|event| self.apply(event)) {
+            if !touched.contains(&name) {
+                touched.push(name);
+            }
+        }
+        touched.iter().map(|name| journal_line(name, self.accounts[*name])).collect()
+    }
+
+    /// The whole ledger as a journal, one line per account.
+    pub fn snapshot(&self) -> String {
+        let mut names: Vec<&String> = self.accounts.keys().collect();
+        names.sort();
+        names.into_iter().map(|name| journal_line(name, self.accounts[name])).collect()
+    }
+
+    /// Only events that move chips touch an account. `Doubled` carries the
+    /// hand's new stake, of which half is the chips just added.
+    fn apply<'e>(&mut self, event: &'e Event) -> Option<&'e str> {
+        if event.name().is_some_and(bot::is_name) {
+            return None;
+        }
+        let name = match event {
+            Event::Joined { name, bankroll, .. } => {
+                self.accounts.insert(name.clone(), Account { bankroll: *bankroll, staked: 0 });
+                name
+            }
+            Event::BetPlaced { name, amount: stake }
+            | Event::Split { name, stake, .. }
+            | Event::Insured { name, stake } => {
+                self.entry(name).stake(*stake);
+                name
+            }
+            Event::Doubled { name, stake, .. } => {
+                self.entry(name).stake(stake / 2);
+                name
+            }
+            Event::InsuranceSettled { name, stake, bankroll, .. } => {
+                let account = self.entry(name);
+                account.bankroll = *bankroll;
+                account.staked = account.staked.saturating_sub(*stake);
+                name
+            }
+            Event::Settled { name, bankroll, .. } => {
+                *self.entry(name) = Account { bankroll: *bankroll, staked: 0 };
+                name
+            }
+            // A wager left on the felt is forfeited, as the table rules it.
+            Event::Left { name } => {
+                self.entry(name).staked = 0;
+                self.in_use.remove(name);
+                name
+            }
+            Event::OutOfChips { name }
+            | Event::SeatReleased { name, .. }
+            | Event::StoppedWatching { name } => {
+                self.in_use.remove(name);
+                return None;
+            }
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    fn entry(&mut self, name: &str) -> &mut Account {
+        self.accounts.entry(name.to_string()).or_default()
+    }
+}
+
+/// Names go last so that they may contain spaces; the transport has already
+/// stripped control characters, newlines included.
+fn journal_line(name: &str, account: Account) -> String {
+    format!("{} {} {}\n", account.bankroll, account.staked, name)
+}
+
+fn parse_line(line: &str) -> Option<(String, Account)> {
+    let mut fields = line.trim_end_matches('\n').splitn(3, ' ');
+    let bankroll = fields.next()?.parse().ok()?;
+    let staked = fields.next()?.parse().ok()?;
+    let name = fields.next().filter(|name| !name.is_empty())?;
+    Some((name.to_string(), Account { bankroll, staked }))
+}
+
+/// The journal file. Appends are synced before they return.
+pub struct Journal {
+    file: File,
+}
+
+impl Journal {
+    /// Opens the journal at `path`, creating it if need be, along with the
+    /// accounts it holds. The file is compacted to one line per account on the
+    /// way in, through a temporary file renamed over it, so it grows with the
+    /// player list rather than the game history, a kill during compaction
+    /// leaves the old journal whole, and a torn last line from the previous
+    /// run is gone before anything is appended after it.
+    pub fn open(path: &Path) -> io::Result<(Journal, Ledger)> {
+        let journal = match fs::read(path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(error),
+        };
+        let ledger = Ledger::replay(&journal);
+        let mut compacting = path.as_os_str().to_owned();
+        compacting.push(".compacting");
+        let mut fresh = File::create(&compacting)?;
+        fresh.write_all(ledger.snapshot().as_bytes())?;
+        fresh.sync_all()?;
+        fs::rename(&compacting, path)?;
+        sync_parent(path);
+        let file = OpenOptions::new().append(true).open(path)?;
+        Ok((Journal { file }, ledger))
+    }
+
+    /// Returns once the lines are on disk, not merely in the page cache.
+    pub fn append(&mut self, lines: &str) -> io::Result<()> {
+        if lines.is_empty() {
+            return Ok(());
+        }
+        self.file.write_all(lines.as_bytes())?;
+        self.file.sync_data()
+    }
+}
+
+/// Makes the rename durable. Best effort: not every platform can open a
+/// directory, and the old journal is still a correct one to replay.
+fn sync_parent(path: &Path) {
+    let parent = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    let _ = File::open(parent.unwrap_or(Path::new("."))).and_then(|dir| dir.sync_all());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::fixtures::house;
+    use crate::rules::Outcome;
+    use std::path::PathBuf;
+
+    fn joined(name: &str, bankroll: u32) -> Event {
+        Event::Joined { name: name.into(), bankroll, bot: false }
+    }
+
+    fn bet(name: &str, amount: u32) -> Event {
+        Event::BetPlaced { name: name.into(), amount }
+    }
+
+    fn settled(name: &str, hand: usize, bankroll: u32) -> Event {
+        Event::Settled { name: name.into(), hand, outcome: Outcome::Win, payout: 0, bankroll }
+    }
+
+    fn scratch(test: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "blackjack-ledger-{}-{}.journal",
+            std::process::id(),
+            test
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn a_bet_moves_chips_from_the_bankroll_to_the_felt() {
+        let mut ledger = Ledger::default();
+        assert_eq!(ledger.record(&[joined("Ada", 200)]), "200 0 Ada\n");
+        assert_eq!(ledger.record(&[bet("Ada", 20)]), "180 20 Ada\n");
+        assert_eq!(ledger.record(&[settled("Ada", 0, 220)]), "220 0 Ada\n");
+    }
+
+    #[test]
+    fn a_doubled_stake_adds_only_the_second_wager() {
+        let mut ledger = Ledger::default();
+        ledger.record(&[joined("Ada", 200), bet("Ada", 20)]);
+        ledger.record(&[Event::Doubled { name: "Ada".into(), hand: 0, stake: 40 }]);
+        assert_eq!(ledger.account("Ada"), Some(Account { bankroll: 160, staked: 40 }));
+    }
+
+    #[test]
+    fn one_batch_writes_one_line_per_account_with_its_final_state() {
+        let mut ledger = Ledger::default();
+        ledger.record(&[joined("Ada", 200), joined("Bo", 100), bet("Ada", 10), bet("Bo", 10)]);
+        let split = Event::Split { name: "Ada".into(), hand: 0, stake: 10 };
+        ledger.record(&[split]);
+        let lines =
+            ledger.record(&[settled("Ada", 0, 190), settled("Ada", 1, 210), settled("Bo", 0, 90)]);
+        assert_eq!(lines, "210 0 Ada\n90 0 Bo\n");
+    }
+
+    #[test]
+    fn replay_refunds_a_round_the_server_never_settled() {
+        let ledger = Ledger::replay("200 0 Ada\n180 20 Ada\n100 0 Bo Jangles\n");
+        assert_eq!(ledger.account("Ada"), Some(Account { bankroll: 200, staked: 0 }));
+        assert_eq!(ledger.account("Bo Jangles").map(|account| account.bankroll), Some(100));
+    }
+
+    #[test]
+    fn a_torn_last_line_is_ignored_and_the_one_before_it_stands() {
+        let ledger = Ledger::replay("200 0 Ada\n220 0 Ad");
+        assert_eq!(ledger.account("Ada").map(|account| account.bankroll), Some(200));
+        assert_eq!(ledger.account("Ad"), None);
+    }
+
+    #[test]
+    fn leaving_mid_round_forfeits_the_stake_and_frees_the_name() {
+        let mut ledger = Ledger::default();
+        assert_eq!(ledger.check_out("Ada", &house()), Ok(200));
+        assert_eq!(ledger.check_out("Ada", &house()), Err(LedgerError::InUse));
+        ledger.record(&[joined("Ada", 200), bet("Ada", 50)]);
+        assert_eq!(ledger.record(&[Event::Left { name: "Ada".into() }]), "150 0 Ada\n");
+        assert_eq!(ledger.check_out("Ada", &house()), Ok(150));
+    }
+
+    #[test]
+    fn a_seat_given_up_for_sitting_out_frees_the_name() {
+        let mut ledger = Ledger::default();
+        ledger.check_out("Ada", &house()).expect("free");
+        ledger.record(&[joined("Ada", 200)]);
+        assert_eq!(ledger.record(&[Event::SeatReleased { name: "Ada".into(), rounds: 5 }]), "");
+        assert_eq!(ledger.check_out("Ada", &house()), Ok(200));
+    }
+
+    #[test]
+    fn the_bots_names_are_nobodys_account() {
+        let mut ledger = Ledger::default();
+        assert_eq!(ledger.check_out("bot 3", &house()), Err(LedgerError::Reserved));
+        assert_eq!(ledger.check_out("Bot Jangles", &house()), Ok(200));
+        let bot = Event::Joined { name: "Bot 3".into(), bankroll: 200, bot: true };
+        assert_eq!(ledger.record(&[bot, bet("Bot 3", 10), settled("Bot 3", 0, 210)]), "");
+        assert_eq!(ledger.account("Bot 3"), None);
+    }
+
+    #[test]
+    fn a_broke_account_comes_back_with_what_it_has_and_no_more() {
+        let mut ledger = Ledger::replay("3 0 Ada\n");
+        assert_eq!(ledger.check_out("Ada", &house()), Ok(3));
+        ledger.check_in("Ada");
+        assert_eq!(ledger.check_out("Ada", &house()), Ok(3));
+        assert_eq!(ledger.snapshot(), "3 0 Ada\n", "no chips were made for her");
+    }
+
+    #[test]
+    fn a_reopened_journal_holds_the_balance_a_crash_interrupted() {
+        let path = scratch("reopen");
+        let (mut journal, mut ledger) = Journal::open(&path).expect("a fresh journal");
+        journal.append(&ledger.record(&[joined("Ada", 200)])).expect("written");
+        journal.append(&ledger.record(&[bet("Ada", 30)])).expect("written");
+        drop(journal);
+        let (_, ledger) = Journal::open(&path).expect("the journal reopens");
+        assert_eq!(ledger.account("Ada"), Some(Account { bankroll: 200, staked: 0 }));
+        assert_eq!(fs::read_to_string(&path).expect("compacted"), "200 0 Ada\n");
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -3,11 +3,21 @@
 //! The rules live here and know nothing about sockets: [`table::Table`] takes
 //! a [`table::Command`] and returns [`table::Event`]s, which makes every rule
 //! in the game testable without a network. `src/main.rs` is only a transport
-//! that moves lines between TCP clients and one owning task.
+//! that moves lines between TCP clients, the [`lobby::Lobby`], and one owning
+//! task per table.
 
+pub mod analysis;
+pub mod bot;
 pub mod card;
+pub mod chat;
 pub mod config;
+pub mod count;
 pub mod hand;
+pub mod ledger;
+pub mod lobby;
+pub mod policy;
 pub mod protocol;
 pub mod rules;
+pub mod sim;
+pub mod strategy;
 pub mod table;
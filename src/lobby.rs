@@ -0,0 +1,286 @@
+//! The lobby: which tables are open, at what stakes, and how full they are.
+//!
+//! Bookkeeping only, like [`crate::table`]: the tasks that own each table live
+//! in `src/main.rs`, and tell the lobby when a seat is taken or given up.
+
+use crate::rules::Rules;
+use crate::table::{MAX_SEATS, MAX_SPECTATORS};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Tables are numbered from 1 in the order they were opened.
+pub type TableId = usize;
+
+/// Every table is a task and a shoe, and any client may open one, so the
+/// floor has a fixed size.
+pub const MAX_TABLES: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LobbyCommand {
+    List,
+    Join(TableId),
+    Watch(TableId),
+    Create(TableSpec),
+}
+
+/// What sets one table apart from the house rules. Anything left `None` is
+/// taken from the house, so an empty spec opens a table on the house rules.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TableSpec {
+    pub min_bet: Option<u32>,
+    pub dealer_hits_soft_17: Option<bool>,
+}
+
+impl TableSpec {
+    /// Reads words like `25 h17`, in any order. A number is the minimum bet;
+    /// `s17` and `h17` pick the dealer's soft 17 rule. Saying either twice is
+    /// refused rather than letting the last word win.
+    pub fn from_words(words: &[&str]) -> Option<Self> {
+        let mut spec = TableSpec::default();
+        for word in words {
+            match word.to_lowercase().as_str() {
+                "s17" if spec.dealer_hits_soft_17.is_none() => {
+                    spec.dealer_hits_soft_17 = Some(false)
+                }
+                "h17" if spec.dealer_hits_soft_17.is_none() => {
+                    spec.dealer_hits_soft_17 = Some(true)
+                }
+                chips if spec.min_bet.is_none() => spec.min_bet = Some(chips.parse().ok()?),
+                _ => return None,
+            }
+        }
+        Some(spec)
+    }
+
+    /// The rules this table plays by. Its minimum bet has to leave room under
+    /// the house maximum and be coverable by a fresh bankroll, the same checks
+    /// the house minimum passed at startup.
+    pub fn apply(&self, house: &Rules) -> Result<Rules, LobbyError> {
+        let mut rules = *house;
+        rules.min_bet = self.min_bet.unwrap_or(house.min_bet);
+        rules.dealer_hits_soft_17 = self.dealer_hits_soft_17.unwrap_or(house.dealer_hits_soft_17);
+        let highest = house.max_bet.min(house.starting_bankroll);
+        if !(1..=highest).contains(&rules.min_bet) {
+            return Err(LobbyError::StakesOutOfRange { highest });
+        }
+        Ok(rules)
+    }
+}
+
+/// The configuration form: `25/h17`, slash-separated so that a list of specs
+/// can sit in one environment variable.
+impl FromStr for TableSpec {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split('/').map(str::trim).filter(|w| !w.is_empty()).collect();
+        TableSpec::from_words(&words).ok_or(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Listing {
+    pub id: TableId,
+    pub rules: Rules,
+    pub seated: usize,
+    pub watching: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LobbyError {
+    NoSuchTable(TableId),
+    TableFull(TableId),
+    GalleryFull(TableId),
+    TooManyTables,
+    StakesOutOfRange { highest: u32 },
+    CannotCover { min_bet: u32, bankroll: u32 },
+}
+
+impl fmt::Display for LobbyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSuchTable(id) => write!(f, "there is no table {}", id),
+            Self::TableFull(id) => write!(f, "table {} is full ({} seats)", id, MAX_SEATS),
+            Self::GalleryFull(id) => {
+                write!(f, "table {} has no room for more spectators ({})", id, MAX_SPECTATORS)
+            }
+            Self::TooManyTables => write!(f, "the floor is full ({} tables)", MAX_TABLES),
+            Self::StakesOutOfRange { highest } => {
+                write!(f, "the minimum bet must be between 1 and {}", highest)
+            }
+            Self::CannotCover { min_bet, bankroll } => {
+                write!(f, "the minimum bet there is {} and you have {}", min_bet, bankroll)
+            }
+        }
+    }
+}
+
+impl Error for LobbyError {}
+
+pub struct Lobby {
+    house: Rules,
+    tables: Vec<Listing>,
+}
+
+impl Lobby {
+    pub fn new(house: Rules) -> Self {
+        Lobby { house, tables: Vec::new() }
+    }
+
+    pub fn listings(&self) -> &[Listing] {
+        &self.tables
+    }
+
+    /// Opens a table on the house rules as amended by `spec`. Tables are never
+    /// closed, so ids are never reused.
+    pub fn open(&mut self, spec: &TableSpec) -> Result<Listing, LobbyError> {
+        if self.tables.len() >= MAX_TABLES {
+            return Err(LobbyError::TooManyTables);
+        }
+        let rules = spec.apply(&self.house)?;
+        let listing = Listing { id: self.tables.len() + 1, rules, seated: 0, watching: 0 };
+        self.tables.push(listing);
+        Ok(listing)
+    }
+
+    /// Opens a table for a player to sit at, so not one whose minimum they
+    /// could not bet.
+    pub fn create(&mut self, spec: &TableSpec, bankroll: u32) -> Result<Listing, LobbyError> {
+        let min_bet = spec.apply(&self.house)?.min_bet;
+        if bankroll < min_bet {
+            return Err(LobbyError::CannotCover { min_bet, bankroll });
+        }
+        self.open(spec)
+    }
+
+    /// The table a player asked for, if it has a seat free as far as the lobby
+    /// knows and they can cover its minimum. The table itself has the final
+    /// word: two players can race for its last seat, and it refuses the loser.
+    pub fn seat(&self, id: TableId, bankroll: u32) -> Result<Listing, LobbyError> {
+        let listing = self.find(id).ok_or(LobbyError::NoSuchTable(id))?;
+        if listing.seated >= MAX_SEATS {
+            return Err(LobbyError::TableFull(id));
+        }
+        let min_bet = listing.rules.min_bet;
+        if bankroll < min_bet {
+            return Err(LobbyError::CannotCover { min_bet, bankroll });
+        }
+        Ok(*listing)
+    }
+
+    /// The table a player asked to watch. No chips are needed to watch, and
+    /// as with a seat, the table has the final word on the last place.
+    pub fn watch(&self, id: TableId) -> Result<Listing, LobbyError> {
+        let listing = self.find(id).ok_or(LobbyError::NoSuchTable(id))?;
+        if listing.watching >= MAX_SPECTATORS {
+            return Err(LobbyError::GalleryFull(id));
+        }
+        Ok(*listing)
+    }
+
+    pub fn occupancy(&mut self, id: TableId, seated: usize, watching: usize) {
+        if let Some(listing) = self.tables.iter_mut().find(|listing| listing.id == id) {
+            listing.seated = seated;
+            listing.watching = watching;
+        }
+    }
+
+    fn find(&self, id: TableId) -> Option<&Listing> {
+        self.tables.iter().find(|listing| listing.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::fixtures::house;
+
+    fn spec(words: &str) -> TableSpec {
+        TableSpec::from_words(&words.split_whitespace().collect::<Vec<_>>()).expect("a valid spec")
+    }
+
+    #[test]
+    fn an_empty_spec_opens_a_table_on_the_house_rules() {
+        let mut lobby = Lobby::new(house());
+        let listing = lobby.open(&TableSpec::default()).expect("room on the floor");
+        assert_eq!(listing, Listing { id: 1, rules: house(), seated: 0, watching: 0 });
+    }
+
+    #[test]
+    fn a_five_dollar_s17_table_runs_beside_a_twenty_five_dollar_h17_one() {
+        let mut lobby = Lobby::new(house());
+        lobby.open(&spec("s17")).expect("room");
+        let high = lobby.open(&spec("25 h17")).expect("room");
+        assert_eq!(high.id, 2);
+        assert_eq!((high.rules.min_bet, high.rules.dealer_hits_soft_17), (25, true));
+        let low = lobby.listings()[0].rules;
+        assert_eq!((low.min_bet, low.dealer_hits_soft_17), (5, false));
+    }
+
+    #[test]
+    fn spec_words_come_in_any_order_but_only_once_each() {
+        assert_eq!(spec("h17 25"), spec("25 h17"));
+        assert_eq!(TableSpec::from_words(&["s17", "h17"]), None);
+        assert_eq!(TableSpec::from_words(&["25", "50"]), None);
+        assert_eq!(TableSpec::from_words(&["cheap"]), None);
+    }
+
+    #[test]
+    fn the_configuration_form_separates_words_with_a_slash() {
+        assert_eq!("25/h17".parse(), Ok(spec("25 h17")));
+        assert_eq!("".parse(), Ok(TableSpec::default()));
+        assert_eq!("25/x".parse::<TableSpec>(), Err(()));
+    }
+
+    #[test]
+    fn a_minimum_a_fresh_bankroll_cannot_cover_is_refused() {
+        let mut lobby = Lobby::new(house());
+        let error = lobby.open(&spec("250")).unwrap_err();
+        assert_eq!(error, LobbyError::StakesOutOfRange { highest: 200 });
+        assert_eq!(lobby.open(&spec("0")), Err(LobbyError::StakesOutOfRange { highest: 200 }));
+        assert!(lobby.listings().is_empty());
+    }
+
+    #[test]
+    fn the_floor_holds_at_most_sixteen_tables() {
+        let mut lobby = Lobby::new(house());
+        (0..MAX_TABLES).for_each(|_| {
+            lobby.open(&TableSpec::default()).expect("room");
+        });
+        assert_eq!(lobby.open(&TableSpec::default()), Err(LobbyError::TooManyTables));
+    }
+
+    #[test]
+    fn a_table_the_lobby_knows_is_full_is_not_offered() {
+        let mut lobby = Lobby::new(house());
+        lobby.open(&TableSpec::default()).expect("room");
+        lobby.occupancy(1, MAX_SEATS - 1, 0);
+        assert_eq!(lobby.seat(1, 200).expect("one seat left").seated, MAX_SEATS - 1);
+        lobby.occupancy(1, MAX_SEATS, 0);
+        assert_eq!(lobby.seat(1, 200), Err(LobbyError::TableFull(1)));
+        assert_eq!(lobby.seat(2, 200), Err(LobbyError::NoSuchTable(2)));
+    }
+
+    #[test]
+    fn a_full_table_can_still_be_watched_until_its_gallery_is_full() {
+        let mut lobby = Lobby::new(house());
+        lobby.open(&spec("25")).expect("room");
+        lobby.occupancy(1, MAX_SEATS, MAX_SPECTATORS - 1);
+        assert_eq!(lobby.watch(1).expect("one place left").watching, MAX_SPECTATORS - 1);
+        lobby.occupancy(1, MAX_SEATS, MAX_SPECTATORS);
+        assert_eq!(lobby.watch(1), Err(LobbyError::GalleryFull(1)));
+        assert_eq!(lobby.watch(2), Err(LobbyError::NoSuchTable(2)));
+    }
+
+    #[test]
+    fn a_player_is_not_seated_at_stakes_they_cannot_cover() {
+        let mut lobby = Lobby::new(house());
+        lobby.open(&spec("25")).expect("room");
+        let short = LobbyError::CannotCover { min_bet: 25, bankroll: 20 };
+        assert_eq!(lobby.seat(1, 20), Err(short));
+        assert_eq!(lobby.create(&spec("25"), 20), Err(short));
+        assert_eq!(lobby.listings().len(), 1);
+        assert_eq!(lobby.create(&spec("10"), 20).expect("covered").id, 2);
+    }
+}
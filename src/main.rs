@@ -1,35 +1,111 @@
-//! TCP front end for the blackjack table.
+//! TCP, TLS and WebSocket front end for the blackjack floor.
 //!
-//! One task owns the [`Table`] and is the only thing that mutates it, so the
-//! rules need no locks. Every connection is two tasks — a reader that turns
-//! lines into commands and a writer that drains a bounded mailbox — so a
-//! client that has stopped reading blocks only its own writer, never the
-//! reader that is still feeding commands to the table.
-
-use blackjack_rust::config::Config;
-use blackjack_rust::protocol::{self, GREETING, HELP};
+//! One task owns the [`Lobby`], one task owns each [`Table`], and one thread
+//! owns the [`Ledger`] of accounts, and each is the only thing that mutates
+//! what it owns, so the rules need no locks. Every
+//! connection is two tasks — a reader that turns lines into commands and a
+//! writer that drains a bounded mailbox — so a client that has stopped reading
+//! blocks only its own writer, never the reader that is still feeding commands
+//! to the table.
+//!
+//! A TLS client is a TCP client once the handshake is done. A browser
+//! connects over WebSocket instead, one text frame per line, and is
+//! served by exactly the same code once the frames are unwrapped.
+//!
+//! Mailboxes carry [`Line`]s rather than text, and each writer renders them in
+//! its own client's [`Mode`], so a table broadcasts once to text and JSON
+//! clients alike.
+
+use blackjack_rust::bot::Bots;
+use blackjack_rust::chat::{Chat, Throttle};
+use blackjack_rust::config::{Config, Tls};
+use blackjack_rust::count::System;
+use blackjack_rust::ledger::{Journal, Ledger, LedgerError};
+use blackjack_rust::lobby::{Listing, Lobby, LobbyCommand, LobbyError, TableId};
+use blackjack_rust::protocol::{self, Line, Login, Mode, Play, Refusal, Stage};
 use blackjack_rust::rules::Rules;
-use blackjack_rust::table::{Command, Event, SeatId, Table, TableError};
+use blackjack_rust::table::{Clocks, Command, Event, SeatId, Table, TableError};
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
 use rand::rngs::StdRng;
 use rand::SeedableRng;
+use rustls_pemfile::Item;
 use std::collections::HashMap;
 use std::error::Error;
+use std::fs::File;
+use std::future::Future;
 use std::io;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::sync::mpsc::{self, Receiver, Sender, WeakSender};
+use tokio::sync::oneshot;
+use tokio::time::{self, MissedTickBehavior};
+use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
 
 /// Bounded so that one wedged client cannot make the table allocate forever.
 const MAILBOX_DEPTH: usize = 64;
 const MAX_NAME_LEN: usize = 16;
+/// How often each table's clocks are read, and so how late a timeout can be.
+const TICK: Duration = Duration::from_secs(1);
+
+type Clients = HashMap<SeatId, Sender<Line>>;
+/// Resume tokens, each naming the seat it can reclaim.
+type Tokens = HashMap<String, SeatId>;
+/// One half of a byte stream, plain TCP or TLS over it.
+type ReadHalf = Box<dyn AsyncRead + Send + Unpin>;
+type WriteHalf = Box<dyn AsyncWrite + Send + Unpin>;
 
-type Clients = HashMap<SeatId, Sender<String>>;
+/// Where a connection's lines come from.
+enum Incoming {
+    Stream(Lines<BufReader<ReadHalf>>),
+    WebSocket(SplitStream<WebSocketStream<TcpStream>>),
+}
+
+/// Where a connection's rendered lines go.
+enum Outgoing {
+    Stream(WriteHalf),
+    WebSocket(SplitSink<WebSocketStream<TcpStream>, Message>),
+}
 
 enum Input {
-    Join { name: String, outbox: Sender<String> },
+    Join {
+        name: String,
+        bankroll: u32,
+        outbox: Sender<Line>,
+    },
+    /// The bankroll comes along in case they sit down later.
+    Watch {
+        name: String,
+        bankroll: u32,
+        outbox: Sender<Line>,
+    },
+    /// Answered only if the token still holds a seat.
+    Resume {
+        token: String,
+        outbox: Sender<Line>,
+        reply: oneshot::Sender<()>,
+    },
     Play(Command),
+    Chat(Chat),
+    /// Questions about the asker's own play, answered to them alone.
+    Hint,
+    Stats,
+    Quiz,
+    Count {
+        running: i32,
+        true_count: Option<i32>,
+    },
+    Away,
     Leave,
+    /// From the table's own interval, not from a player: `seat` is empty.
+    Tick(Instant),
 }
 
 struct Request {
@@ -37,6 +113,77 @@ struct Request {
     input: Input,
 }
 
+enum LobbyRequest {
+    Visit {
+        command: LobbyCommand,
+        bankroll: u32,
+        reply: oneshot::Sender<Result<Answer, LobbyError>>,
+    },
+    Occupancy {
+        table: TableId,
+        seated: usize,
+        watching: usize,
+    },
+    Find {
+        table: TableId,
+        reply: oneshot::Sender<Option<Sender<Request>>>,
+    },
+}
+
+enum Answer {
+    Listings(Vec<Listing>),
+    Table(Destination),
+}
+
+/// A table to sit at, or to watch.
+struct Destination {
+    listing: Listing,
+    requests: Sender<Request>,
+    watching: bool,
+}
+
+enum LedgerRequest {
+    CheckOut {
+        name: String,
+        reply: oneshot::Sender<Result<u32, LedgerError>>,
+    },
+    CheckIn {
+        name: String,
+    },
+    /// Answered once the events are on disk.
+    Record {
+        events: Vec<Event>,
+        written: oneshot::Sender<()>,
+    },
+}
+
+/// The lobby's registry plus what it cannot hold: the channel into each
+/// table's task, and the channels each new table reports to.
+struct Floor {
+    lobby: Lobby,
+    tables: HashMap<TableId, Sender<Request>>,
+    updates: Sender<LobbyRequest>,
+    ledger: Sender<LedgerRequest>,
+    grace_secs: u64,
+    clocks: Clocks,
+    trainer: Option<System>,
+    bots: usize,
+    bot_policies: Vec<String>,
+}
+
+/// The two desks a connection deals with before it reaches a table.
+#[derive(Clone)]
+struct Desks {
+    lobby: Sender<LobbyRequest>,
+    ledger: Sender<LedgerRequest>,
+    grace_secs: u64,
+}
+
+enum Arrival {
+    Account { name: String, bankroll: u32 },
+    Resumed(Sender<Request>),
+}
+
 #[tokio::main]
 async fn main() {
     if let Err(error) = run().await {
@@ -47,138 +194,723 @@ async fn main() {
 
 async fn run() -> Result<(), Box<dyn Error>> {
     let config = Config::from_env()?;
+    let acceptor = config.tls.as_ref().map(tls_acceptor).transpose()?;
+    let desks = open_floor(&config)?;
     let listener = TcpListener::bind(&config.bind_addr).await?;
-    println!("blackjack table open on {}", config.bind_addr);
-    let (requests, inbox) = mpsc::channel(MAILBOX_DEPTH);
-    tokio::spawn(run_table(config.rules, inbox));
-    accept_forever(listener, requests).await
+    let encrypted = match &config.tls {
+        Some(tls) => Some(TcpListener::bind(&tls.addr).await?),
+        None => None,
+    };
+    let browsers = match &config.ws_addr {
+        Some(addr) => Some(TcpListener::bind(addr).await?),
+        None => None,
+    };
+    println!("blackjack floor open on {}", config.bind_addr);
+    if let (Some(browsers), Some(addr)) = (browsers, &config.ws_addr) {
+        println!("and to browsers on ws://{}", addr);
+        tokio::spawn(accept_forever(browsers, desks.clone(), serve_websocket));
+    }
+    if let (Some(encrypted), Some(acceptor), Some(tls)) = (encrypted, acceptor, &config.tls) {
+        println!("and over TLS on {}", tls.addr);
+        let serve = move |stream, peer, desks| serve_tls(stream, peer, desks, acceptor.clone());
+        tokio::spawn(accept_forever(encrypted, desks.clone(), serve));
+    }
+    accept_forever(listener, desks, serve_tcp).await;
+    Ok(())
+}
+
+/// Reads the certificate chain and key named by the config. Any problem is
+/// fatal at startup and names the variable whose file is at fault.
+fn tls_acceptor(tls: &Tls) -> Result<TlsAcceptor, String> {
+    let certs: Vec<Certificate> = pem_items("BLACKJACK_TLS_CERT", &tls.cert_path)?
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::X509Certificate(der) => Some(Certificate(der)),
+            _ => None,
+        })
+        .collect();
+    if certs.is_empty() {
+        return Err(format!("BLACKJACK_TLS_CERT: no certificate in {}", tls.cert_path));
+    }
+    let key = pem_items("BLACKJACK_TLS_KEY", &tls.key_path)?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::PKCS8Key(der) | Item::RSAKey(der) | Item::ECKey(der) => Some(PrivateKey(der)),
+            _ => None,
+        })
+        .ok_or_else(|| format!("BLACKJACK_TLS_KEY: no private key in {}", tls.key_path))?;
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|error| format!("BLACKJACK_TLS_KEY does not fit the certificate: {}", error))?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn pem_items(var: &str, path: &str) -> Result<Vec<Item>, String> {
+    let file =
+        File::open(path).map_err(|error| format!("{}: cannot open {}: {}", var, path, error))?;
+    rustls_pemfile::read_all(&mut io::BufReader::new(file))
+        .map_err(|error| format!("{}: cannot read {}: {}", var, path, error))
+}
+
+/// Starts the ledger thread and the lobby task, opens the configured tables,
+/// and returns the desks every connection starts at.
+fn open_floor(config: &Config) -> Result<Desks, Box<dyn Error>> {
+    let (journal, accounts) = Journal::open(Path::new(&config.ledger_path))
+        .map_err(|error| format!("cannot open {}: {}", config.ledger_path, error))?;
+    let (ledger, ledger_inbox) = mpsc::channel(MAILBOX_DEPTH);
+    let house = config.rules;
+    thread::spawn(move || run_ledger(journal, accounts, house, ledger_inbox));
+    let (lobby, inbox) = mpsc::channel(MAILBOX_DEPTH);
+    let mut floor = Floor {
+        lobby: Lobby::new(config.rules),
+        tables: HashMap::new(),
+        updates: lobby.clone(),
+        ledger: ledger.clone(),
+        grace_secs: config.reconnect_grace_secs,
+        clocks: config.clocks,
+        trainer: config.trainer,
+        bots: config.bots,
+        bot_policies: config.bot_policies.clone(),
+    };
+    for spec in &config.tables {
+        let listing = floor.lobby.open(spec)?;
+        floor.spawn(listing);
+    }
+    tokio::spawn(run_lobby(floor, inbox));
+    Ok(Desks { lobby, ledger, grace_secs: config.reconnect_grace_secs })
 }
 
 /// EMFILE, ECONNABORTED and friends are conditions of one connection, not of
 /// the listener, so they are logged and skipped. Only bind and config failures
 /// are fatal — dropping six seated players over a transient errno is not.
-async fn accept_forever(
+async fn accept_forever<F>(
     listener: TcpListener,
-    requests: Sender<Request>,
-) -> Result<(), Box<dyn Error>> {
+    desks: Desks,
+    serve: impl Fn(TcpStream, SeatId, Desks) -> F,
+) where
+    F: Future<Output = ()> + Send + 'static,
+{
     loop {
         match listener.accept().await {
             Ok((stream, peer)) => {
-                tokio::spawn(serve(stream, peer.to_string(), requests.clone()));
+                tokio::spawn(serve(stream, peer.to_string(), desks.clone()));
             }
             Err(error) => eprintln!("blackjack: accept failed, still serving: {}", error),
         }
     }
 }
 
-/// The single owner of the table. Commands arrive in order, so the rules run
-/// on one thread with no shared mutable state at all.
-async fn run_table(rules: Rules, mut inbox: Receiver<Request>) {
-    let mut table = Table::new(rules, StdRng::from_entropy());
+/// The single owner of the lobby. It answers from what the tables last told
+/// it and never awaits a table, so a table may await it without a deadlock.
+async fn run_lobby(mut floor: Floor, mut inbox: Receiver<LobbyRequest>) {
+    while let Some(request) = inbox.recv().await {
+        match request {
+            LobbyRequest::Visit { command, bankroll, reply } => {
+                let _ = reply.send(floor.visit(command, bankroll));
+            }
+            LobbyRequest::Occupancy { table, seated, watching } => {
+                floor.lobby.occupancy(table, seated, watching)
+            }
+            LobbyRequest::Find { table, reply } => {
+                let _ = reply.send(floor.tables.get(&table).cloned());
+            }
+        }
+    }
+}
+
+impl Floor {
+    fn visit(&mut self, command: LobbyCommand, bankroll: u32) -> Result<Answer, LobbyError> {
+        let listing = match command {
+            LobbyCommand::List => return Ok(Answer::Listings(self.lobby.listings().to_vec())),
+            LobbyCommand::Join(id) => self.lobby.seat(id, bankroll)?,
+            LobbyCommand::Watch(id) => self.lobby.watch(id)?,
+            LobbyCommand::Create(spec) => {
+                let listing = self.lobby.create(&spec, bankroll)?;
+                self.spawn(listing);
+                listing
+            }
+        };
+        let requests = self.tables[&listing.id].clone();
+        let watching = matches!(command, LobbyCommand::Watch(_));
+        Ok(Answer::Table(Destination { listing, requests, watching }))
+    }
+
+    fn spawn(&mut self, listing: Listing) {
+        let (requests, inbox) = mpsc::channel(MAILBOX_DEPTH);
+        let (lobby, ledger) = (self.updates.clone(), self.ledger.clone());
+        let mut table = Table::new(listing.rules, StdRng::from_entropy()).with_clocks(self.clocks);
+        if let Some(system) = self.trainer {
+            table = table.with_trainer(system);
+        }
+        let bots = Bots::new(self.bots, &self.bot_policies);
+        tokio::spawn(run_table(listing.id, table, bots, inbox, lobby, ledger, self.grace_secs));
+        self.tables.insert(listing.id, requests);
+    }
+}
+
+/// The single owner of the accounts and their journal. Every append waits on
+/// the disk, so this is a thread of its own rather than a task that would hold
+/// up a runtime worker; it never awaits a table either.
+fn run_ledger(
+    mut journal: Journal,
+    mut ledger: Ledger,
+    house: Rules,
+    mut inbox: Receiver<LedgerRequest>,
+) {
+    while let Some(request) = inbox.blocking_recv() {
+        match request {
+            LedgerRequest::CheckOut { name, reply } => {
+                let _ = reply.send(ledger.check_out(&name, &house));
+            }
+            LedgerRequest::CheckIn { name } => ledger.check_in(&name),
+            LedgerRequest::Record { events, written } => {
+                if let Err(error) = journal.append(&ledger.record(&events)) {
+                    // Dealing on would settle rounds the journal cannot keep.
+                    // Stopping here leaves the last line written, which a
+                    // restart replays, refunding whatever was on the felt.
+                    eprintln!("blackjack: cannot write the ledger, shutting down: {}", error);
+                    std::process::exit(1);
+                }
+                let _ = written.send(());
+            }
+        }
+    }
+}
+
+/// The single owner of one table. Commands and clock ticks arrive in order,
+/// so the rules run on one thread with no shared mutable state at all.
+async fn run_table(
+    id: TableId,
+    mut table: Table<StdRng>,
+    mut bots: Bots,
+    mut inbox: Receiver<Request>,
+    lobby: Sender<LobbyRequest>,
+    ledger: Sender<LedgerRequest>,
+    grace_secs: u64,
+) {
     let mut clients = Clients::new();
-    while let Some(Request { seat, input }) = inbox.recv().await {
-        let result = dispatch(&mut table, &mut clients, &seat, input);
+    let mut tokens = Tokens::new();
+    let mut throttle = Throttle::default();
+    let mut reported = (0, 0);
+    let mut clock = time::interval(TICK);
+    // A table stalled behind a slow ledger catches up with one tick, not a
+    // burst of them.
+    clock.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        let Request { seat, input } = tokio::select! {
+            request = inbox.recv() => match request {
+                Some(request) => request,
+                None => return,
+            },
+            now = clock.tick() => {
+                Request { seat: SeatId::new(), input: Input::Tick(now.into_std()) }
+            }
+        };
+        // Chat changes nothing at the table, so it needs none of the
+        // bookkeeping below; it only has to reach everyone in turn.
+        if let Input::Chat(chat) = input {
+            relay(&table, &clients, &mut throttle, &seat, chat);
+            continue;
+        }
+        // Nor does a question, and only the asker hears the answer.
+        if let Some(answer) = answer(&mut table, &seat, &input) {
+            whisper(&clients, &seat, answer);
+            continue;
+        }
+        let arriving = match &input {
+            Input::Join { name, .. } | Input::Watch { name, .. } => Some(name.clone()),
+            _ => None,
+        };
+        let sitting = matches!(input, Input::Join { .. } | Input::Play(Command::Sit));
+        let watching = matches!(input, Input::Watch { .. });
+        let resuming = matches!(input, Input::Resume { .. });
+        let result = dispatch(&mut table, &mut clients, &mut tokens, &seat, input);
+        let arrived = result.is_ok();
+        // On disk before anyone is told, and a refused seat or place in the
+        // gallery hands its account straight back.
+        match (&result, arriving) {
+            (Ok(events), _) if !events.is_empty() => record(&ledger, events.clone()).await,
+            (Err(_), Some(name)) => {
+                let _ = ledger.send(LedgerRequest::CheckIn { name }).await;
+            }
+            _ => {}
+        }
         publish(&clients, &seat, result);
-        // Whoever the rules no longer seat — a refused join, a player out of
-        // chips, a disconnect — loses their mailbox here, which shuts their
-        // socket. Otherwise they linger as spectators reading everyone's cards.
-        clients.retain(|id, _| table.is_seated(id));
+        // The token is for the new seat's eyes only, and a reclaimed seat or a
+        // new spectator is told what it missed.
+        if arrived && (resuming || watching) {
+            if watching {
+                whisper(&clients, &seat, Line::Prompt(Stage::Gallery));
+            }
+            let summary = table.summary(&seat);
+            summary.into_iter().for_each(|event| whisper(&clients, &seat, Line::Event(event)));
+        } else if arrived && sitting {
+            whisper(&clients, &seat, Line::Prompt(Stage::Table));
+            if grace_secs > 0 {
+                let token = resume_token(id);
+                whisper(&clients, &seat, Line::ResumeToken { token: token.clone(), grace_secs });
+                tokens.insert(token, seat.clone());
+            }
+        }
+        // The bots move once the player has, so a round a player is in never
+        // waits on one, and they come and go as players do.
+        match bots.act(&mut table) {
+            Ok(events) if events.is_empty() => {}
+            Ok(events) => {
+                record(&ledger, events.clone()).await;
+                events.into_iter().for_each(|event| announce(&clients, &Line::Event(event)));
+            }
+            Err(error) => eprintln!("blackjack: a bot at table {} was refused: {}", id, error),
+        }
+        // Whoever the table neither seats nor lets watch — a refused join, a
+        // player out of chips, a disconnect — loses their mailbox here, which
+        // shuts their socket.
+        clients.retain(|id, _| table.is_seated(id) || table.is_watching(id));
+        tokens.retain(|_, id| table.is_seated(id));
+        throttle.retain(|id| clients.contains_key(id));
+        if (table.occupancy(), table.audience()) != reported {
+            reported = (table.occupancy(), table.audience());
+            let (seated, watching) = reported;
+            let _ = lobby.send(LobbyRequest::Occupancy { table: id, seated, watching }).await;
+        }
+    }
+}
+
+/// Waits until the ledger thread has the events synced to the journal, so
+/// that a kill can never lose a settlement a player has already been told of.
+/// The wait is an await, so a slow disk holds up this table and nothing else.
+async fn record(ledger: &Sender<LedgerRequest>, events: Vec<Event>) {
+    let (written, synced) = oneshot::channel();
+    if ledger.send(LedgerRequest::Record { events, written }).await.is_ok() {
+        let _ = synced.await;
     }
 }
 
+fn answer(table: &mut Table<StdRng>, seat: &str, input: &Input) -> Option<Line> {
+    let answer = match *input {
+        Input::Hint => table.hint(seat).map(|(hand, action)| Line::Hint { hand, action }),
+        Input::Stats => table.accuracy(seat).map(Line::Stats),
+        Input::Quiz => table.quiz(seat).map(Line::Event),
+        Input::Count { running, true_count } => {
+            table.answer_count(seat, running, true_count).map(Line::CountChecked)
+        }
+        _ => return None,
+    };
+    Some(answer.unwrap_or_else(|error| Line::Refused(Refusal::Table(error))))
+}
+
 fn dispatch(
     table: &mut Table<StdRng>,
     clients: &mut Clients,
+    tokens: &mut Tokens,
     seat: &str,
     input: Input,
 ) -> Result<Vec<Event>, TableError> {
     match input {
-        Input::Join { name, outbox } => {
+        Input::Join { name, bankroll, outbox } => {
+            clients.insert(seat.to_string(), outbox);
+            table.join_with_bankroll(seat, &name, bankroll)
+        }
+        Input::Watch { name, bankroll, outbox } => {
             clients.insert(seat.to_string(), outbox);
-            table.join(seat, &name)
+            table.watch(seat, &name, bankroll)
+        }
+        Input::Resume { token, outbox, reply } => {
+            let Some(held) = tokens.get(&token) else {
+                return Ok(Vec::new());
+            };
+            let events = table.resume(held, seat)?;
+            tokens.insert(token, seat.to_string());
+            clients.insert(seat.to_string(), outbox);
+            let _ = reply.send(());
+            Ok(events)
         }
         Input::Play(command) => table.apply(seat, command),
+        Input::Chat(_) | Input::Hint | Input::Stats | Input::Quiz | Input::Count { .. } => {
+            Ok(Vec::new())
+        }
+        Input::Away => table.step_away(seat),
         Input::Leave => table.leave(seat),
+        Input::Tick(now) => table.tick(now),
+    }
+}
+
+/// Goes to everyone at the table, spectators included, from anyone it seats
+/// or lets watch. A speaker over their rate is told so and nobody else hears.
+fn relay(
+    table: &Table<StdRng>,
+    clients: &Clients,
+    throttle: &mut Throttle,
+    seat: &str,
+    chat: Chat,
+) {
+    let Some(name) = table.name_of(seat) else {
+        return;
+    };
+    if !throttle.allow(seat, Instant::now()) {
+        return whisper(clients, seat, Line::Refused(Refusal::ChattingTooFast));
     }
+    announce(clients, &Line::Chat { name: name.to_string(), chat });
+}
+
+/// Prefixed with the table so the reconnecting reader knows where to take it;
+/// the rest comes from the OS-seeded thread RNG and cannot be guessed.
+fn resume_token(table: TableId) -> String {
+    format!("{}-{:016x}", table, rand::random::<u64>())
 }
 
 fn publish(clients: &Clients, origin: &str, result: Result<Vec<Event>, TableError>) {
     let events = match result {
         Ok(events) => events,
-        Err(error) => return whisper(clients, origin, &format!("{}\n", error)),
+        Err(error) => return whisper(clients, origin, Line::Refused(Refusal::Table(error))),
     };
-    events.iter().for_each(|event| announce(clients, &protocol::render(event)));
+    events.into_iter().for_each(|event| announce(clients, &Line::Event(event)));
 }
 
 /// Never awaits: a client that has stopped reading loses lines rather than
 /// stalling the round for everyone else.
-fn announce(clients: &Clients, line: &str) {
+fn announce(clients: &Clients, line: &Line) {
     clients.values().for_each(|outbox| {
-        let _ = outbox.try_send(line.to_string());
+        let _ = outbox.try_send(line.clone());
     });
 }
 
-fn whisper(clients: &Clients, seat: &str, line: &str) {
+fn whisper(clients: &Clients, seat: &str, line: Line) {
     if let Some(outbox) = clients.get(seat) {
-        let _ = outbox.try_send(line.to_string());
+        let _ = outbox.try_send(line);
     }
 }
 
-async fn serve(stream: TcpStream, seat: SeatId, requests: Sender<Request>) {
+async fn serve_tcp(stream: TcpStream, seat: SeatId, desks: Desks) {
     let (reader, writer) = stream.into_split();
+    serve_stream(Box::new(reader), Box::new(writer), seat, desks).await;
+}
+
+/// The handshake runs on the connection's own task, so a client that never
+/// finishes it holds up nobody else. Seats are prefixed like WebSocket ones.
+async fn serve_tls(stream: TcpStream, peer: String, desks: Desks, acceptor: TlsAcceptor) {
+    let seat = format!("tls:{}", peer);
+    let stream = match acceptor.accept(stream).await {
+        Ok(stream) => stream,
+        Err(error) => {
+            eprintln!("connection {} failed the TLS handshake: {}", seat, error);
+            return;
+        }
+    };
+    let (reader, writer) = tokio::io::split(stream);
+    serve_stream(Box::new(reader), Box::new(writer), seat, desks).await;
+}
+
+async fn serve_stream(reader: ReadHalf, writer: WriteHalf, seat: SeatId, desks: Desks) {
+    let lines = Incoming::Stream(BufReader::new(reader).lines());
+    serve(lines, Outgoing::Stream(writer), seat, desks).await;
+}
+
+/// The seat is told apart from a TCP client's, which could in principle share
+/// its peer address.
+async fn serve_websocket(stream: TcpStream, peer: String, desks: Desks) {
+    let seat = format!("ws:{}", peer);
+    let socket = match tokio_tungstenite::accept_async(stream).await {
+        Ok(socket) => socket,
+        Err(error) => {
+            eprintln!("connection {} failed the WebSocket handshake: {}", seat, error);
+            return;
+        }
+    };
+    let (frames_out, frames_in) = socket.split();
+    serve(Incoming::WebSocket(frames_in), Outgoing::WebSocket(frames_out), seat, desks).await;
+}
+
+async fn serve(mut lines: Incoming, writer: Outgoing, seat: SeatId, desks: Desks) {
     let (outbox, mailbox) = mpsc::channel(MAILBOX_DEPTH);
     tokio::spawn(drain(mailbox, writer));
-    let _ = outbox.try_send(GREETING.to_string());
-    if let Err(error) = read_commands(reader, &seat, &requests, outbox).await {
+    let _ = outbox.try_send(Line::Prompt(Stage::Name));
+    let mut mode = Mode::Text;
+    let (requests, mailbox) = match sit_down(&mut lines, &seat, &desks, outbox, &mut mode).await {
+        Ok(Some(seated)) => seated,
+        Ok(None) => return,
+        Err(error) => {
+            eprintln!("connection {} closed in the lobby: {}", seat, error);
+            return;
+        }
+    };
+    if let Err(error) = read_commands(lines, &seat, &requests, mailbox, mode).await {
         eprintln!("connection {} closed: {}", seat, error);
     }
-    submit(&requests, &seat, Input::Leave).await;
+    hold_seat(&requests, &seat, desks.grace_secs).await;
+}
+
+/// Keeps a dropped player's seat through the grace window, then gives it up.
+/// If they resumed in the meantime the seat belongs to their new connection,
+/// and this `Leave` names a seat that no longer exists.
+async fn hold_seat(requests: &Sender<Request>, seat: &str, grace_secs: u64) {
+    if grace_secs > 0 {
+        submit(requests, seat, Input::Away).await;
+        tokio::time::sleep(Duration::from_secs(grace_secs)).await;
+    }
+    submit(requests, seat, Input::Leave).await;
 }
 
 /// Ends when the table drops this client's mailbox, and shuts the write half
 /// on the way out so a dropped player is told the connection is over.
-async fn drain(mut mailbox: Receiver<String>, mut writer: OwnedWriteHalf) {
+///
+/// The writer keeps its own copy of the mode, switched by the acknowledgement
+/// the reader queues, so every line queued before a switch is rendered the
+/// way the client expected when it was sent.
+async fn drain(mut mailbox: Receiver<Line>, mut writer: Outgoing) {
+    let mut mode = Mode::Text;
     while let Some(line) = mailbox.recv().await {
-        if writer.write_all(line.as_bytes()).await.is_err() {
+        if let Line::Mode(switched) = line {
+            mode = switched;
+        }
+        if writer.send(protocol::render_line(&line, mode)).await.is_err() {
             return;
         }
     }
-    let _ = writer.shutdown().await;
+    writer.close().await;
+}
+
+impl Incoming {
+    /// A WebSocket text frame is one line. Pings are answered by the library
+    /// underneath, and binary frames mean nothing here, so both are skipped.
+    async fn next_line(&mut self) -> io::Result<Option<String>> {
+        let frames = match self {
+            Self::Stream(lines) => return lines.next_line().await,
+            Self::WebSocket(frames) => frames,
+        };
+        while let Some(frame) = frames.next().await {
+            match frame.map_err(|error| io::Error::new(io::ErrorKind::Other, error))? {
+                Message::Text(line) => return Ok(Some(line)),
+                Message::Close(_) => return Ok(None),
+                _ => continue,
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Outgoing {
+    /// Each line is a frame of its own, without the newline a terminal needs.
+    async fn send(&mut self, rendered: String) -> io::Result<()> {
+        match self {
+            Self::Stream(writer) => writer.write_all(rendered.as_bytes()).await,
+            Self::WebSocket(frames) => {
+                let frame = Message::Text(rendered.trim_end_matches('\n').to_string());
+                let sent = frames.send(frame).await;
+                sent.map_err(|error| io::Error::new(io::ErrorKind::Other, error))
+            }
+        }
+    }
+
+    /// A close frame for a browser, so it is told the seat is gone rather
+    /// than left to notice the socket drop.
+    async fn close(&mut self) {
+        match self {
+            Self::Stream(writer) => {
+                let _ = writer.shutdown().await;
+            }
+            Self::WebSocket(frames) => {
+                let _ = frames.close().await;
+            }
+        }
+    }
+}
+
+/// Takes a name, then lobby commands until the player picks, opens or asks to
+/// watch a table, and sends them there. `None` means they hung up first.
+///
+/// Hands the table the *only* strong sender for this mailbox and returns a
+/// weak one, so that when the table drops the seat the channel closes, the
+/// writer shuts the socket, and the reader stops reading from a player who has
+/// no table to talk to.
+async fn sit_down(
+    lines: &mut Incoming,
+    seat: &str,
+    desks: &Desks,
+    outbox: Sender<Line>,
+    mode: &mut Mode,
+) -> io::Result<Option<(Sender<Request>, WeakSender<Line>)>> {
+    let (name, bankroll) = match log_in(lines, seat, desks, &outbox, mode).await? {
+        Some(Arrival::Account { name, bankroll }) => (name, bankroll),
+        Some(Arrival::Resumed(requests)) => return Ok(Some((requests, outbox.downgrade()))),
+        None => return Ok(None),
+    };
+    let _ = outbox.try_send(Line::Prompt(Stage::Lobby));
+    let destination = match choose_table(lines, &desks.lobby, bankroll, &outbox, mode).await {
+        Ok(Some(chosen)) => chosen,
+        unseated => {
+            let _ = desks.ledger.send(LedgerRequest::CheckIn { name }).await;
+            return unseated.map(|_| None);
+        }
+    };
+    let Destination { listing, requests, watching } = destination;
+    let _ = outbox.try_send(Line::Listing(listing));
+    let mailbox = outbox.downgrade();
+    let input = match watching {
+        true => Input::Watch { name, bankroll, outbox },
+        false => Input::Join { name, bankroll, outbox },
+    };
+    submit(&requests, seat, input).await;
+    Ok(Some((requests, mailbox)))
+}
+
+/// A name is an account, and one connection at a time may hold it. A resume
+/// token instead reclaims a held seat, account and all.
+async fn log_in(
+    lines: &mut Incoming,
+    seat: &str,
+    desks: &Desks,
+    outbox: &Sender<Line>,
+    mode: &mut Mode,
+) -> io::Result<Option<Arrival>> {
+    while let Some(line) = lines.next_line().await? {
+        if switch_mode(&line, mode, outbox) {
+            continue;
+        }
+        let name = match protocol::parse_login(&line, *mode) {
+            Some(Login::Name(name)) => seat_name(&name, seat),
+            Some(Login::Resume(token)) => match resume(&desks.lobby, seat, token, outbox).await {
+                Some(requests) => return Ok(Some(Arrival::Resumed(requests))),
+                None => {
+                    let _ = outbox.try_send(Line::Refused(Refusal::UnknownToken));
+                    let _ = outbox.try_send(Line::Prompt(Stage::Name));
+                    continue;
+                }
+            },
+            None => {
+                let _ = outbox.try_send(Line::Unrecognised(Stage::Name));
+                continue;
+            }
+        };
+        let (reply, answer) = oneshot::channel();
+        let request = LedgerRequest::CheckOut { name: name.clone(), reply };
+        if desks.ledger.send(request).await.is_err() {
+            return Ok(None);
+        }
+        match answer.await {
+            Ok(Ok(bankroll)) => {
+                let _ = outbox.try_send(Line::Account { name: name.clone(), bankroll });
+                return Ok(Some(Arrival::Account { name, bankroll }));
+            }
+            Ok(Err(error)) => {
+                let _ = outbox.try_send(Line::Refused(Refusal::Ledger(error)));
+            }
+            Err(_) => return Ok(None),
+        }
+    }
+    Ok(None)
+}
+
+/// The table keeps the clone of `outbox` it is sent only if the token holds
+/// a seat, and says so; otherwise it drops it and the reply with it.
+async fn resume(
+    lobby: &Sender<LobbyRequest>,
+    seat: &str,
+    token: String,
+    outbox: &Sender<Line>,
+) -> Option<Sender<Request>> {
+    let table = token.split_once('-')?.0.parse().ok()?;
+    let (reply, answer) = oneshot::channel();
+    lobby.send(LobbyRequest::Find { table, reply }).await.ok()?;
+    let requests = answer.await.ok()??;
+    let (reply, answer) = oneshot::channel();
+    submit(&requests, seat, Input::Resume { token, outbox: outbox.clone(), reply }).await;
+    answer.await.ok().map(|()| requests)
+}
+
+async fn choose_table(
+    lines: &mut Incoming,
+    lobby: &Sender<LobbyRequest>,
+    bankroll: u32,
+    outbox: &Sender<Line>,
+    mode: &mut Mode,
+) -> io::Result<Option<Destination>> {
+    while let Some(line) = lines.next_line().await? {
+        if switch_mode(&line, mode, outbox) {
+            continue;
+        }
+        let Some(command) = protocol::parse_lobby(&line, *mode) else {
+            let _ = outbox.try_send(Line::Unrecognised(Stage::Lobby));
+            continue;
+        };
+        match visit(lobby, command, bankroll).await {
+            Some(Ok(Answer::Listings(listings))) => listings.into_iter().for_each(|listing| {
+                let _ = outbox.try_send(Line::Listing(listing));
+            }),
+            Some(Ok(Answer::Table(destination))) => return Ok(Some(destination)),
+            Some(Err(error)) => {
+                let _ = outbox.try_send(Line::Refused(Refusal::Lobby(error)));
+            }
+            None => return Ok(None),
+        }
+    }
+    Ok(None)
+}
+
+/// `None` only if the lobby task is gone, which means the server is too.
+async fn visit(
+    lobby: &Sender<LobbyRequest>,
+    command: LobbyCommand,
+    bankroll: u32,
+) -> Option<Result<Answer, LobbyError>> {
+    let (reply, answer) = oneshot::channel();
+    lobby.send(LobbyRequest::Visit { command, bankroll, reply }).await.ok()?;
+    answer.await.ok()
 }
 
-/// Hands the table the *only* strong sender for this mailbox and keeps a weak
-/// one, so that when the table drops the seat the channel closes, the writer
-/// shuts the socket, and this loop stops reading from a player who has no
-/// table to talk to.
 async fn read_commands(
-    reader: OwnedReadHalf,
+    mut lines: Incoming,
     seat: &str,
     requests: &Sender<Request>,
-    outbox: Sender<String>,
+    mailbox: WeakSender<Line>,
+    mut mode: Mode,
 ) -> io::Result<()> {
-    let mut lines = BufReader::new(reader).lines();
-    let Some(greeting) = lines.next_line().await? else {
-        return Ok(());
-    };
-    let name = seat_name(&greeting, seat);
-    let _ = outbox.try_send(HELP.to_string());
-    let mailbox = outbox.downgrade();
-    submit(requests, seat, Input::Join { name, outbox }).await;
     while let Some(line) = lines.next_line().await? {
         let Some(outbox) = mailbox.upgrade() else {
             return Ok(());
         };
-        forward(&line, seat, requests, &outbox).await;
+        if switch_mode(&line, &mut mode, &outbox) {
+            continue;
+        }
+        forward(&line, seat, requests, &outbox, mode).await;
     }
     Ok(())
 }
 
-async fn forward(line: &str, seat: &str, requests: &Sender<Request>, outbox: &Sender<String>) {
-    let Some(command) = protocol::parse_command(line) else {
-        let _ = outbox.try_send(HELP.to_string());
-        return;
+async fn forward(
+    line: &str,
+    seat: &str,
+    requests: &Sender<Request>,
+    outbox: &Sender<Line>,
+    mode: Mode,
+) {
+    let input = match protocol::parse_play(line, mode) {
+        Some(Play::Command(command)) => Input::Play(command),
+        Some(Play::Chat(chat)) => Input::Chat(chat),
+        Some(Play::Hint) => Input::Hint,
+        Some(Play::Stats) => Input::Stats,
+        Some(Play::Quiz) => Input::Quiz,
+        Some(Play::Count { running, true_count }) => Input::Count { running, true_count },
+        None => {
+            let _ = outbox.try_send(Line::Unrecognised(Stage::Table));
+            return;
+        }
+    };
+    submit(requests, seat, input).await;
+}
+
+/// Handles `mode json` and `mode text` wherever the client is. The reader
+/// parses in the new mode from the next line, and the writer renders in it
+/// from the acknowledgement on.
+fn switch_mode(line: &str, mode: &mut Mode, outbox: &Sender<Line>) -> bool {
+    let Some(switched) = protocol::parse_mode(line) else {
+        return false;
     };
-    submit(requests, seat, Input::Play(command)).await;
+    *mode = switched;
+    let _ = outbox.try_send(Line::Mode(switched));
+    true
 }
 
 /// Awaits on purpose: inbound backpressure should slow a chatty client down.
@@ -187,9 +919,179 @@ async fn submit(requests: &Sender<Request>, seat: &str, input: Input) {
 }
 
 fn seat_name(raw: &str, seat: &str) -> String {
-    let name: String = raw.trim().chars().filter(|c| !c.is_control()).take(MAX_NAME_LEN).collect();
+    let name = protocol::sanitize(raw, MAX_NAME_LEN);
     if name.is_empty() {
         return format!("guest-{}", seat);
     }
     name
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::net::SocketAddr;
+    use tokio::io::AsyncBufReadExt;
+    use tokio::time::timeout;
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore, ServerName};
+    use tokio_rustls::TlsConnector;
+
+    type Browser = WebSocketStream<tokio_tungstenite::MaybeTlsStream<TcpStream>>;
+
+    fn scratch(test: &str, file: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "blackjack-main-{}-{}.{}",
+            std::process::id(),
+            test,
+            file
+        ));
+        let _ = fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    /// A floor on its own journal.
+    fn floor(test: &str) -> Desks {
+        let ledger_path = scratch(test, "journal");
+        let config = Config::read(|var| match var {
+            "BLACKJACK_BIND_ADDR" => Some("127.0.0.1:0".to_string()),
+            "BLACKJACK_LEDGER_PATH" => Some(ledger_path.clone()),
+            _ => None,
+        })
+        .expect("a valid config");
+        open_floor(&config).expect("the floor opens")
+    }
+
+    async fn listen<F>(
+        desks: &Desks,
+        serve: impl Fn(TcpStream, SeatId, Desks) -> F + Send + 'static,
+    ) -> SocketAddr
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("a port");
+        let addr = listener.local_addr().expect("bound");
+        tokio::spawn(accept_forever(listener, desks.clone(), serve));
+        addr
+    }
+
+    /// A floor with a plain and a WebSocket listener, on ephemeral ports.
+    async fn open(test: &str) -> (SocketAddr, SocketAddr) {
+        let desks = floor(test);
+        (listen(&desks, serve_tcp).await, listen(&desks, serve_websocket).await)
+    }
+
+    async fn frame_until(browser: &mut Browser, wanted: &str) {
+        let seen = timeout(Duration::from_secs(5), async {
+            while let Some(frame) = browser.next().await {
+                if frame.expect("a frame") == Message::Text(wanted.to_string()) {
+                    return true;
+                }
+            }
+            false
+        });
+        assert!(seen.await.expect("in time"), "never saw {:?}", wanted);
+    }
+
+    async fn say(browser: &mut Browser, line: &str) {
+        browser.send(Message::Text(line.to_string())).await.expect("sent");
+    }
+
+    #[tokio::test]
+    async fn a_browser_and_a_terminal_play_at_the_same_table() {
+        let (terminals, browsers) = open("shared-table").await;
+        let (mut browser, _) = tokio_tungstenite::connect_async(format!("ws://{}", browsers))
+            .await
+            .expect("connected");
+        say(&mut browser, "Ada").await;
+        say(&mut browser, "join 1").await;
+        frame_until(&mut browser, "Ada sits down with 200 chips").await;
+
+        let mut terminal = TcpStream::connect(terminals).await.expect("connected");
+        terminal.write_all(b"Bob\njoin 1\nbet 10\n").await.expect("sent");
+        frame_until(&mut browser, "Bob sits down with 200 chips").await;
+        frame_until(&mut browser, "Bob bets 10").await;
+
+        say(&mut browser, "say hi Bob").await;
+        let mut lines = BufReader::new(terminal).lines();
+        let heard = timeout(Duration::from_secs(5), async {
+            while let Some(line) = lines.next_line().await.expect("readable") {
+                if line == "<Ada> hi Bob" {
+                    return true;
+                }
+            }
+            false
+        });
+        assert!(heard.await.expect("in time"), "the terminal hears the browser");
+    }
+
+    #[tokio::test]
+    async fn a_browser_can_speak_json_one_object_per_frame() {
+        let (_, browsers) = open("json-frames").await;
+        let (mut browser, _) = tokio_tungstenite::connect_async(format!("ws://{}", browsers))
+            .await
+            .expect("connected");
+        say(&mut browser, "mode json").await;
+        say(&mut browser, r#"{"cmd":"login","name":"Ada"}"#).await;
+        frame_until(&mut browser, r#"{"bankroll":200,"name":"Ada","type":"account","v":1}"#).await;
+    }
+
+    /// A self-signed certificate for `localhost`, written where the config
+    /// can name it, and a client that trusts it and nothing else.
+    fn self_signed(test: &str) -> (Tls, TlsConnector) {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])
+            .expect("a certificate");
+        let tls = Tls {
+            addr: "127.0.0.1:0".to_string(),
+            cert_path: scratch(test, "cert.pem"),
+            key_path: scratch(test, "key.pem"),
+        };
+        fs::write(&tls.cert_path, cert.serialize_pem().expect("PEM")).expect("written");
+        fs::write(&tls.key_path, cert.serialize_private_key_pem()).expect("written");
+        let mut roots = RootCertStore::empty();
+        roots.add(&Certificate(cert.serialize_der().expect("DER"))).expect("trusted");
+        let client = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        (tls, TlsConnector::from(Arc::new(client)))
+    }
+
+    #[tokio::test]
+    async fn a_tls_client_is_served_like_a_plain_one() {
+        let (tls, connector) = self_signed("tls-login");
+        let acceptor = tls_acceptor(&tls).expect("the files load");
+        let desks = floor("tls-login");
+        let serve = move |stream, peer, desks| serve_tls(stream, peer, desks, acceptor.clone());
+        let addr = listen(&desks, serve).await;
+        let stream = TcpStream::connect(addr).await.expect("connected");
+        let localhost = ServerName::try_from("localhost").expect("a name");
+        let mut stream = connector.connect(localhost, stream).await.expect("handshake");
+        stream.write_all(b"Ada\n").await.expect("sent");
+        let mut lines = BufReader::new(stream).lines();
+        let answered = timeout(Duration::from_secs(5), async {
+            while let Some(line) = lines.next_line().await.expect("readable") {
+                if line == "Ada, you have 200 chips" {
+                    return true;
+                }
+            }
+            false
+        });
+        assert!(answered.await.expect("in time"), "the account comes back encrypted");
+    }
+
+    #[test]
+    fn unreadable_tls_files_are_refused_at_startup_by_name() {
+        let (mut tls, _) = self_signed("tls-files");
+        let cert = tls.cert_path.clone();
+        tls.cert_path = scratch("tls-files", "missing.pem");
+        let error = tls_acceptor(&tls).err().expect("no certificate file");
+        assert!(error.starts_with("BLACKJACK_TLS_CERT: cannot open"), "{}", error);
+        tls.cert_path = tls.key_path.clone();
+        let error = tls_acceptor(&tls).err().expect("a key is not a certificate");
+        assert!(error.starts_with("BLACKJACK_TLS_CERT: no certificate"), "{}", error);
+        tls.cert_path = cert.clone();
+        tls.key_path = cert;
+        let error = tls_acceptor(&tls).err().expect("a certificate is not a key");
+        assert!(error.starts_with("BLACKJACK_TLS_KEY: no private key"), "{}", error);
+    }
+}
@@ -0,0 +1,175 @@
+//! How a seat plays when nobody is typing for it: the simulator's players,
+//! and the house's bots.
+//!
+//! A [`Policy`] sees what a player at the table could — the [`Turn`] the
+//! table puts to the seat and the table's rules — and answers with a move.
+//! The simulator asks for nothing else, so a new way of playing is one more
+//! implementation here, and it is measured against exactly the table the
+//! server deals.
+
+use crate::rules::{self, Rules};
+use crate::strategy::{self, Action, Options};
+use crate::table::Turn;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+/// A policy may keep what it likes between rounds, but nothing it keeps may
+/// change its moves: a sharded simulation hands each thread one policy for
+/// whichever shards it draws, so a policy that learned from earlier rounds
+/// would play a shard differently depending on the threads. A memo of
+/// answers, like [`BasicStrategy`]'s, is fine. A policy that rolls dice, like
+/// [`RandomPlay`], keeps the promise by rolling them from [`Policy::reseed`].
+pub trait Policy {
+    /// The opening wager. Unless the policy says otherwise, the smallest the
+    /// table takes that a natural pays in whole chips: 3:2 on an odd wager
+    /// rounds half a chip away, which would be measured as house edge.
+    fn wager(&mut self, rules: &Rules) -> u32 {
+        let per = rules.natural_pays.per.max(1);
+        let even = (rules.min_bet + per - 1) / per * per;
+        if even <= rules.max_bet {
+            even
+        } else {
+            rules.min_bet
+        }
+    }
+
+    /// A move the turn offers. Hitting and standing always are; anything
+    /// else must be in `turn.options`, or the table refuses it.
+    fn play(&mut self, turn: &Turn, rules: &Rules) -> Action;
+
+    /// A seed of the shard's own, given as a simulation starts each shard.
+    /// Dice rolled from it fall the same on whichever thread plays the shard;
+    /// a policy without dice has nothing to do with it.
+    fn reseed(&mut self, _seed: u64) {}
+}
+
+/// The policies the simulator and the bots can be asked for by name.
+pub const NAMES: [&str; 4] = ["basic", "stand", "dealer", "random"];
+
+/// One of [`NAMES`], fresh.
+pub fn named(name: &str) -> Option<Box<dyn Policy + Send>> {
+    match name.trim().to_lowercase().as_str() {
+        "basic" => Some(Box::<BasicStrategy>::default()),
+        "stand" => Some(Box::new(AlwaysStand)),
+        "dealer" => Some(Box::new(MimicDealer)),
+        "random" => Some(Box::new(RandomPlay::new(StdRng::from_entropy()))),
+        _ => None,
+    }
+}
+
+/// Plays [`strategy::best_action`]. Every answer depends only on the cards
+/// in view and the moves on offer, and the same few hundred questions come
+/// up over and over, so each is worked out once.
+#[derive(Default)]
+pub struct BasicStrategy {
+    known: HashMap<(Vec<u16>, u16, Options), Action>,
+}
+
+impl Policy for BasicStrategy {
+    fn play(&mut self, turn: &Turn, rules: &Rules) -> Action {
+        let mut points: Vec<u16> =
+            turn.player.cards().iter().map(|card| card.rank.points()).collect();
+        points.sort_unstable();
+        let key = (points, turn.up.rank.points(), turn.options);
+        *self
+            .known
+            .entry(key)
+            .or_insert_with(|| strategy::best_action(&turn.player, turn.up, rules, turn.options))
+    }
+}
+
+/// Never draws, so never busts, and leaves every dealer bust on the table.
+pub struct AlwaysStand;
+
+impl Policy for AlwaysStand {
+    fn play(&mut self, _turn: &Turn, _rules: &Rules) -> Action {
+        Action::Stand
+    }
+}
+
+/// Draws exactly as the dealer must. The dealer's edge is that the player
+/// busts first, which this measures on its own.
+pub struct MimicDealer;
+
+impl Policy for MimicDealer {
+    fn play(&mut self, turn: &Turn, rules: &Rules) -> Action {
+        match rules::dealer_should_hit(&turn.player, rules.dealer_hits_soft_17) {
+            true => Action::Hit,
+            false => Action::Stand,
+        }
+    }
+}
+
+/// Any move on offer, each as likely as the next: the floor every other
+/// policy is measured from.
+pub struct RandomPlay {
+    rng: StdRng,
+}
+
+impl RandomPlay {
+    pub fn new(rng: StdRng) -> Self {
+        RandomPlay { rng }
+    }
+}
+
+impl Policy for RandomPlay {
+    fn play(&mut self, turn: &Turn, _rules: &Rules) -> Action {
+        let Options { double, splits, surrender } = turn.options;
+        let mut moves = vec![Action::Stand, Action::Hit];
+        moves.extend(double.then_some(Action::Double));
+        moves.extend((splits > 0).then_some(Action::Split));
+        moves.extend(surrender.then_some(Action::Surrender));
+        moves[self.rng.gen_range(0..moves.len())]
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Rank::*;
+    use crate::hand::fixtures::{card, hand};
+    use crate::rules::fixtures::house;
+    use crate::rules::Odds;
+
+    fn turn(ranks: &[crate::card::Rank]) -> Turn {
+        Turn { hand: 0, player: hand(ranks), up: card(Six), options: Options::default() }
+    }
+
+    #[test]
+    fn the_default_wager_is_the_smallest_a_natural_pays_in_whole_chips() {
+        assert_eq!(AlwaysStand.wager(&house()), 6);
+        let six_to_five = Rules { min_bet: 6, natural_pays: Odds { win: 6, per: 5 }, ..house() };
+        assert_eq!(AlwaysStand.wager(&six_to_five), 10);
+        assert_eq!(AlwaysStand.wager(&Rules { max_bet: 5, ..house() }), 5, "the limit comes first");
+    }
+
+    #[test]
+    fn mimicking_the_dealer_hits_soft_17_only_where_the_dealer_does() {
+        let h17 = Rules { dealer_hits_soft_17: true, ..house() };
+        assert_eq!(MimicDealer.play(&turn(&[Ace, Six]), &house()), Action::Stand);
+        assert_eq!(MimicDealer.play(&turn(&[Ace, Six]), &h17), Action::Hit);
+        assert_eq!(MimicDealer.play(&turn(&[Ten, Six]), &house()), Action::Hit);
+        assert_eq!(BasicStrategy::default().play(&turn(&[Ten, Six]), &house()), Action::Stand);
+    }
+
+    #[test]
+    fn random_play_tries_every_move_on_offer_and_nothing_else() {
+        let mut random = RandomPlay::new(StdRng::seed_from_u64(7));
+        let pair = Turn {
+            options: Options { double: true, splits: 1, surrender: false },
+            ..turn(&[Eight, Eight])
+        };
+        let mut played: Vec<Action> = (0..200).map(|_| random.play(&pair, &house())).collect();
+        played.sort_by_key(|action| *action as u8);
+        played.dedup();
+        assert_eq!(played, [Action::Stand, Action::Hit, Action::Double, Action::Split]);
+        let hit = turn(&[Ten, Two]);
+        let plays = (0..50).map(|_| random.play(&hit, &house()));
+        assert!(plays.into_iter().all(|action| matches!(action, Action::Stand | Action::Hit)));
+    }
+}
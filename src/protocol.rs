@@ -1,15 +1,234 @@
-//! The line protocol spoken over the socket: text in, text out.
+//! The line protocol spoken over the socket: text in, text out, or one JSON
+//! object per line each way once a client asks for it with `mode json`.
 //!
 //! Everything a client sees is produced here, so [`crate::table`] never has to
-//! know that it is being played over a network.
+//! know that it is being played over a network. The JSON half lives in
+//! [`json`]; this module decides which of the two a line goes through.
+
+pub mod json;
 
 use crate::card::Card;
+use crate::chat::{Chat, MAX_CHAT_LEN};
+use crate::count::Verdict;
 use crate::hand::Total;
+use crate::ledger::LedgerError;
+use crate::lobby::{Listing, LobbyCommand, LobbyError, TableSpec};
 use crate::rules::Outcome;
-use crate::table::{Command, Event};
+use crate::strategy::{Accuracy, Action};
+use crate::table::{Command, Event, TableError, MAX_SEATS};
+use std::fmt;
+
+pub const GREETING: &str =
+    "Welcome to the casino. What is your name? (Or resume <token> to reclaim a seat.)\n";
+pub const LOBBY_HELP: &str = "Lobby: list | join <table> | watch <table> | \
+    create [<min bet>] [s17|h17]. Disconnect to leave.\n";
+pub const WATCH_HELP: &str = "You are watching. Type sit to take a seat when one is free, \
+    or say <text> to chat. Disconnect to leave.\n";
+pub const HELP: &str = "Commands: bet <chips> | hit | stand | double | split | \
+    insure <chips> | no | even | surrender | hint | stats | quiz | \
+    count <running> [<true>] | sit out | sit in | say <text> | /me <text>. \
+    Disconnect to leave.\n";
+
+/// Which protocol a connection speaks. Every connection starts in text, and
+/// `mode json` or `mode text` switches both directions at once.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    #[default]
+    Text,
+    Json,
+}
+
+/// How far a connection has got, which decides what it may send next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Name,
+    Lobby,
+    Table,
+    Gallery,
+}
+
+/// Everything the server can say to a client, before it is put into words or
+/// JSON. The writer renders each one in whatever mode the client is in by the
+/// time it comes off the mailbox.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Line {
+    /// What the client may send next.
+    Prompt(Stage),
+    /// The client sent something that means nothing at this stage.
+    Unrecognised(Stage),
+    /// Acknowledges a switch, in the mode switched to.
+    Mode(Mode),
+    Account {
+        name: String,
+        bankroll: u32,
+    },
+    Listing(Listing),
+    ResumeToken {
+        token: String,
+        grace_secs: u64,
+    },
+    Event(Event),
+    Chat {
+        name: String,
+        chat: Chat,
+    },
+    /// Basic strategy for one of the asker's hands, whispered to them alone.
+    Hint {
+        hand: usize,
+        action: Action,
+    },
+    /// The asker's own grade so far, whispered like a hint.
+    Stats(Accuracy),
+    /// An answer to a count check, marked.
+    CountChecked(Verdict),
+    Refused(Refusal),
+}
+
+/// Why something a client asked for did not happen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Refusal {
+    Table(TableError),
+    Lobby(LobbyError),
+    Ledger(LedgerError),
+    UnknownToken,
+    ChattingTooFast,
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Table(error) => write!(f, "{}", error),
+            Self::Lobby(error) => write!(f, "{}", error),
+            Self::Ledger(error) => write!(f, "{}", error),
+            Self::UnknownToken => write!(f, "no seat is held for that token"),
+            Self::ChattingTooFast => write!(f, "you are chatting too fast -- give it a moment"),
+        }
+    }
+}
+
+/// What a client sends before it has an account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Login {
+    Name(String),
+    Resume(String),
+}
+
+/// What a client sends from a seat or the gallery.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Play {
+    Command(Command),
+    Chat(Chat),
+    /// Asks what basic strategy would do with the hand to play.
+    Hint,
+    /// Asks how the seat's moves so far measure up against basic strategy.
+    Stats,
+    /// Asks a counting trainer for a count check.
+    Quiz,
+    /// Answers a count check.
+    Count {
+        running: i32,
+        true_count: Option<i32>,
+    },
+}
+
+/// `mode json` or `mode text`, accepted in either mode so that a client never
+/// has to know which one it was left in.
+pub fn parse_mode(line: &str) -> Option<Mode> {
+    let lowered = line.trim().to_lowercase();
+    match lowered.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["mode", "json"] => Some(Mode::Json),
+        ["mode", "text"] => Some(Mode::Text),
+        _ => json::parse_mode(line),
+    }
+}
+
+/// In text any line that is not a resume is a name, so only JSON can fail.
+pub fn parse_login(line: &str, mode: Mode) -> Option<Login> {
+    match mode {
+        Mode::Text => Some(match parse_resume(line) {
+            Some(token) => Login::Resume(token),
+            None => Login::Name(line.to_string()),
+        }),
+        Mode::Json => json::parse_login(line),
+    }
+}
+
+pub fn parse_lobby(line: &str, mode: Mode) -> Option<LobbyCommand> {
+    match mode {
+        Mode::Text => parse_lobby_command(line),
+        Mode::Json => json::parse_lobby_command(line),
+    }
+}
+
+pub fn parse_play(line: &str, mode: Mode) -> Option<Play> {
+    match mode {
+        Mode::Text => parse_question(line)
+            .or_else(|| parse_chat(line).map(Play::Chat))
+            .or_else(|| parse_command(line).map(Play::Command)),
+        Mode::Json => json::parse_play(line),
+    }
+}
+
+/// The lines a seat sends about its own play, which the table answers to
+/// the asker alone.
+fn parse_question(line: &str) -> Option<Play> {
+    let lowered = line.trim().to_lowercase();
+    match lowered.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["hint"] => Some(Play::Hint),
+        ["stats"] => Some(Play::Stats),
+        ["quiz"] => Some(Play::Quiz),
+        ["count", running] => {
+            Some(Play::Count { running: running.parse().ok()?, true_count: None })
+        }
+        ["count", running, true_count] => Some(Play::Count {
+            running: running.parse().ok()?,
+            true_count: Some(true_count.parse().ok()?),
+        }),
+        _ => None,
+    }
+}
 
-pub const GREETING: &str = "Welcome to the table. What is your name?\n";
-pub const HELP: &str = "Commands: bet <chips> | hit | stand. Disconnect to leave.\n";
+/// Renders one line for a client in `mode`, newline included.
+pub fn render_line(line: &Line, mode: Mode) -> String {
+    match mode {
+        Mode::Text => render_text(line),
+        Mode::Json => json::render(line),
+    }
+}
+
+fn render_text(line: &Line) -> String {
+    match line {
+        Line::Prompt(stage) | Line::Unrecognised(stage) => help(*stage).to_string(),
+        Line::Mode(Mode::Text) => "Speaking text.\n".to_string(),
+        Line::Mode(Mode::Json) => "Speaking JSON.\n".to_string(),
+        Line::Account { name, bankroll } => render_account(name, *bankroll),
+        Line::Listing(listing) => render_listing(listing),
+        Line::ResumeToken { token, grace_secs } => render_resume_token(token, *grace_secs),
+        Line::Event(event) => render(event),
+        Line::Chat { name, chat } => render_chat(name, chat),
+        Line::Hint { hand: 0, action } => format!("Basic strategy says {}\n", action),
+        Line::Hint { hand, action } => {
+            format!("Basic strategy says {} on hand {}\n", action, hand + 1)
+        }
+        Line::Stats(accuracy) => render_accuracy("You", accuracy),
+        Line::CountChecked(verdict) => render_verdict(verdict),
+        Line::Refused(Refusal::Ledger(error)) => format!("{}. What is your name?\n", error),
+        Line::Refused(Refusal::UnknownToken) => "No seat is held for that token.\n".to_string(),
+        Line::Refused(Refusal::ChattingTooFast) => {
+            "You are chatting too fast -- give it a moment\n".to_string()
+        }
+        Line::Refused(refusal) => format!("{}\n", refusal),
+    }
+}
+
+fn help(stage: Stage) -> &'static str {
+    match stage {
+        Stage::Name => GREETING,
+        Stage::Lobby => LOBBY_HELP,
+        Stage::Table => HELP,
+        Stage::Gallery => WATCH_HELP,
+    }
+}
 
 /// Parses one line of client input. Anything unrecognised is `None` and gets
 /// a usage hint rather than being silently swallowed.
@@ -18,30 +237,228 @@ pub fn parse_command(line: &str) -> Option<Command> {
     match lowered.split_whitespace().collect::<Vec<_>>().as_slice() {
         ["hit"] | ["h"] => Some(Command::Hit),
         ["stand"] | ["s"] => Some(Command::Stand),
+        ["double"] | ["d"] => Some(Command::Double),
+        ["split"] | ["p"] => Some(Command::Split),
+        ["insure", chips] | ["insurance", chips] => chips.parse().ok().map(Command::Insure),
+        ["no"] | ["n"] | ["no", "insurance"] => Some(Command::Decline),
+        ["even"] | ["even", "money"] => Some(Command::EvenMoney),
+        ["surrender"] | ["sur"] => Some(Command::Surrender),
+        ["sit", "out"] => Some(Command::SitOut),
+        ["sit", "in"] => Some(Command::SitIn),
+        ["sit"] => Some(Command::Sit),
         ["bet", chips] | [chips] => chips.parse().ok().map(Command::Bet),
         _ => None,
     }
 }
 
+/// Parses a chat line, `say <text>` or `/me <text>`. The text keeps its case
+/// but loses control characters, so nobody can clear another player's screen
+/// or forge a line of their own.
+pub fn parse_chat(line: &str) -> Option<Chat> {
+    let (verb, text) = line.trim().split_once(char::is_whitespace)?;
+    let text = sanitize(text, MAX_CHAT_LEN);
+    if text.is_empty() {
+        return None;
+    }
+    match verb.to_lowercase().as_str() {
+        "say" => Some(Chat::Say(text)),
+        "/me" => Some(Chat::Emote(text)),
+        _ => None,
+    }
+}
+
+/// Trims `raw`, drops control characters, and keeps at most `max_chars`.
+pub fn sanitize(raw: &str, max_chars: usize) -> String {
+    let kept: String = raw.trim().chars().filter(|c| !c.is_control()).take(max_chars).collect();
+    kept.trim_end().to_string()
+}
+
+pub fn render_chat(name: &str, chat: &Chat) -> String {
+    match chat {
+        Chat::Say(text) => format!("<{}> {}\n", name, text),
+        Chat::Emote(text) => format!("* {} {}\n", name, text),
+    }
+}
+
+/// Parses one line typed in the lobby, before the player has a seat.
+pub fn parse_lobby_command(line: &str) -> Option<LobbyCommand> {
+    let lowered = line.trim().to_lowercase();
+    match lowered.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["list"] | ["l"] | ["tables"] => Some(LobbyCommand::List),
+        ["join", table] | ["j", table] => table.parse().ok().map(LobbyCommand::Join),
+        ["watch", table] | ["w", table] => table.parse().ok().map(LobbyCommand::Watch),
+        ["create", spec @ ..] => TableSpec::from_words(spec).map(LobbyCommand::Create),
+        _ => None,
+    }
+}
+
+/// A dropped player's way back to their seat, typed in place of a name.
+pub fn parse_resume(line: &str) -> Option<String> {
+    match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [verb, token] if verb.eq_ignore_ascii_case("resume") => Some(token.to_string()),
+        _ => None,
+    }
+}
+
+/// Whispered to a player as they sit down, and never shown to anyone else.
+pub fn render_resume_token(token: &str, grace_secs: u64) -> String {
+    format!(
+        "If your connection drops, reconnect and type `resume {}` within {}s to keep your seat\n",
+        token, grace_secs
+    )
+}
+
+/// Said once a name is claimed, so a returning player sees their balance
+/// before choosing stakes.
+pub fn render_account(name: &str, bankroll: u32) -> String {
+    format!("{}, you have {} chips\n", name, bankroll)
+}
+
+/// One line per table: everything a player needs to pick a game before
+/// sitting down at it.
+pub fn render_listing(listing: &Listing) -> String {
+    let rules = &listing.rules;
+    let soft_17 = if rules.dealer_hits_soft_17 { "hits" } else { "stands on" };
+    let watching = match listing.watching {
+        0 => String::new(),
+        watching => format!(", {} watching", watching),
+    };
+    format!(
+        "Table {}: {} to {}, dealer {} soft 17, blackjack pays {} -- {} of {} seats taken{}\n",
+        listing.id,
+        rules.min_bet,
+        rules.max_bet,
+        soft_17,
+        rules.natural_pays,
+        listing.seated,
+        MAX_SEATS,
+        watching
+    )
+}
+
 /// Blackjack is an open-handed game: every card except the dealer's hole card
 /// is face up, so every event is broadcast to the whole table verbatim.
 pub fn render(event: &Event) -> String {
     match event {
-        Event::Joined { name, bankroll } => format!("{} sits down with {} chips\n", name, bankroll),
+        Event::Joined { name, bankroll, bot: false } => {
+            format!("{} sits down with {} chips\n", name, bankroll)
+        }
+        Event::Joined { name, bankroll, bot: true } => {
+            format!("{}, a house bot, sits down with {} chips\n", name, bankroll)
+        }
         Event::Left { name } => format!("{} leaves the table\n", name),
-        Event::BettingOpened { min_bet } => format!("Place your bets (minimum {})\n", min_bet),
+        Event::Watching { name } => format!("{} is watching\n", name),
+        Event::StoppedWatching { name } => format!("{} stops watching\n", name),
+        Event::SteppedAway { name } => {
+            format!("{} has lost the connection -- the seat is held for now\n", name)
+        }
+        Event::Returned { name, bankroll } => format!("{} is back with {} chips\n", name, bankroll),
+        Event::BettingOpened { min_bet, max_bet, natural_pays } => format!(
+            "Place your bets ({} to {}, blackjack pays {})\n",
+            min_bet, max_bet, natural_pays
+        ),
         Event::BetPlaced { name, amount } => format!("{} bets {}\n", name, amount),
-        Event::DealerShows { card } => format!("Dealer shows {} and one down\n", card),
-        Event::TurnStarted { name } => format!("{} to act -- hit or stand?\n", name),
-        Event::Dealt { name, cards, total } => hand_line(name, cards, *total),
-        Event::Bust { name, total } => format!("{} busts with {}\n", name, total.value),
-        Event::Stood { name, total } => format!("{} stands on {}\n", name, score(*total)),
+        Event::DealerShows { card, hole_card: true } => {
+            format!("Dealer shows {} and one down\n", card)
+        }
+        Event::DealerShows { card, .. } => format!("Dealer shows {}\n", card),
+        Event::InsuranceOffered => {
+            "Insurance? insure <chips> for up to half your bet, or no\n".to_string()
+        }
+        Event::SurrenderOffered => {
+            "Surrender before the dealer peeks? surrender, or no\n".to_string()
+        }
+        Event::SurrenderDeclined { name } => format!("{} plays on\n", name),
+        Event::EvenMoneyOffered { name } => format!("{} has blackjack -- even money?\n", name),
+        Event::Insured { name, stake: 0 } => format!("{} declines insurance\n", name),
+        Event::Insured { name, stake } => format!("{} insures for {}\n", name, stake),
+        Event::EvenMoneyTaken { name } => format!("{} takes even money\n", name),
+        Event::DealerChecked => "Dealer checks the hole card -- no blackjack\n".to_string(),
+        Event::InsuranceSettled { name, stake, payout, bankroll } => {
+            let result = match payout {
+                0 => format!("loses {} insurance", stake),
+                paid => format!("collects {} on insurance", paid),
+            };
+            format!("{} {} -- {} chips\n", name, result, bankroll)
+        }
+        Event::TurnStarted { name, hand } => {
+            format!("{} to act\n", who(name, *hand))
+        }
+        Event::Doubled { name, hand, stake } => {
+            format!("{} doubles down for {}\n", who(name, *hand), stake)
+        }
+        Event::Split { name, stake, .. } => format!("{} splits and adds {}\n", name, stake),
+        Event::Surrendered { name } => format!("{} surrenders\n", name),
+        Event::Dealt { name, hand, cards, total } => hand_line(&who(name, *hand), cards, *total),
+        Event::Bust { name, hand, total } => {
+            format!("{} busts with {}\n", who(name, *hand), total.value)
+        }
+        Event::Stood { name, hand, total } => {
+            format!("{} stands on {}\n", who(name, *hand), score(*total))
+        }
         Event::DealerHand { cards, total } => hand_line("Dealer", cards, *total),
         Event::OutOfChips { name } => format!("{} is out of chips and leaves\n", name),
-        Event::Settled { name, outcome, payout, bankroll } => {
-            format!("{} {} -- {} chips\n", name, settlement(*outcome, *payout), bankroll)
+        Event::TurnTimedOut { name } => format!("{} ran out of time\n", name),
+        Event::SatOut { name } => format!("{} sits out\n", name),
+        Event::SatIn { name } => format!("{} sits back in\n", name),
+        Event::SeatReleased { name, rounds } => {
+            format!("{} has sat out {} rounds and gives up the seat\n", name, rounds)
         }
+        Event::Graded { name, accuracy } => render_accuracy(name, accuracy),
+        Event::Shuffled => "The dealer shuffles a fresh shoe\n".to_string(),
+        Event::CountAsked { true_count: true } => {
+            "Count check: the running and true counts? Answer count <running> <true>\n".to_string()
+        }
+        Event::CountAsked { true_count: false } => {
+            "Count check: the running count? Answer count <running>\n".to_string()
+        }
+        Event::Settled { name, hand, outcome, payout, bankroll } => {
+            let result = settlement(*outcome, *payout);
+            format!("{} {} -- {} chips\n", who(name, *hand), result, bankroll)
+        }
+    }
+}
+
+/// A grade as a trainer would read it out: how often the player strayed from
+/// basic strategy, and what straying cost on average.
+fn render_accuracy(who: &str, accuracy: &Accuracy) -> String {
+    format!(
+        "{}: {} moves, {} off basic strategy ({:.0}%), {:.2} chips of expected value given up\n",
+        who,
+        accuracy.decisions,
+        accuracy.mistakes,
+        accuracy.error_rate() * 100.0,
+        accuracy.ev_lost
+    )
+}
+
+fn render_verdict(verdict: &Verdict) -> String {
+    let mark = |right| if right { "right" } else { "wrong" };
+    let mut line = format!(
+        "Running count {} -- you said {}, {}.",
+        verdict.reading.running,
+        verdict.running,
+        mark(verdict.running_right)
+    );
+    if let (Some(exact), Some(right)) = (verdict.reading.true_count, verdict.true_right) {
+        let given = verdict.true_count.map_or("nothing".to_string(), |given| given.to_string());
+        line += &format!(" True count {:.1} -- you said {}, {}.", exact, given, mark(right));
+    }
+    let score = verdict.score;
+    line += &format!(" {} of {} running counts right", score.running_right, score.asked);
+    if verdict.true_right.is_some() {
+        line += &format!(", {} of {} true counts", score.true_right, score.asked);
     }
+    line + "\n"
+}
+
+/// A seat's first hand goes by the player's name alone, which is all an
+/// unsplit seat ever needs; hands added by splitting are numbered from 2.
+fn who(name: &str, hand: usize) -> String {
+    if hand == 0 {
+        return name.to_string();
+    }
+    format!("{} (hand {})", name, hand + 1)
 }
 
 fn hand_line(who: &str, cards: &[Card], total: Total) -> String {
@@ -59,8 +476,11 @@ fn score(total: Total) -> String {
 fn settlement(outcome: Outcome, payout: u32) -> String {
     match outcome {
         Outcome::Blackjack => format!("has blackjack and collects {}", payout),
+        Outcome::EvenMoney => format!("is paid even money, {}", payout),
         Outcome::Win => format!("wins {}", payout),
         Outcome::Push => "pushes".to_string(),
+        Outcome::Surrender => format!("takes back {} of the wager", payout),
+        Outcome::Lose if payout > 0 => format!("loses the opening bet, {} handed back", payout),
         Outcome::Lose => "loses".to_string(),
     }
 }
@@ -69,6 +489,10 @@ fn settlement(outcome: Outcome, payout: u32) -> String {
 mod tests {
     use super::*;
     use crate::card::{Rank, Suit};
+    use crate::count::{Reading, Score};
+    use crate::lobby::Lobby;
+    use crate::rules::fixtures::house;
+    use crate::rules::Odds;
 
     fn card(rank: Rank) -> Card {
         Card { rank, suit: Suit::Hearts }
@@ -82,6 +506,137 @@ mod tests {
         assert_eq!(parse_command("s"), Some(Command::Stand));
     }
 
+    #[test]
+    fn double_and_split_accept_their_one_letter_forms() {
+        assert_eq!(parse_command("double"), Some(Command::Double));
+        assert_eq!(parse_command(" D "), Some(Command::Double));
+        assert_eq!(parse_command("split"), Some(Command::Split));
+        assert_eq!(parse_command("P"), Some(Command::Split));
+    }
+
+    #[test]
+    fn insurance_takes_a_stake_and_no_declines_it() {
+        assert_eq!(parse_command("insure 5"), Some(Command::Insure(5)));
+        assert_eq!(parse_command("insurance 2"), Some(Command::Insure(2)));
+        assert_eq!(parse_command("no"), Some(Command::Decline));
+        assert_eq!(parse_command("no insurance"), Some(Command::Decline));
+        assert_eq!(parse_command("even money"), Some(Command::EvenMoney));
+        assert_eq!(parse_command("insure"), None);
+    }
+
+    #[test]
+    fn surrender_is_spelled_out_or_abbreviated_to_sur() {
+        assert_eq!(parse_command("surrender"), Some(Command::Surrender));
+        assert_eq!(parse_command("SUR"), Some(Command::Surrender));
+    }
+
+    #[test]
+    fn sitting_out_and_in_take_two_words() {
+        assert_eq!(parse_command("sit out"), Some(Command::SitOut));
+        assert_eq!(parse_command("Sit In"), Some(Command::SitIn));
+        assert_eq!(parse_command("sit"), Some(Command::Sit));
+        assert_eq!(parse_command("sit down"), None);
+    }
+
+    #[test]
+    fn chat_keeps_its_case_and_loses_control_characters() {
+        assert_eq!(parse_chat("say Nice hand, Ada"), Some(Chat::Say("Nice hand, Ada".into())));
+        assert_eq!(parse_chat("SAY hi"), Some(Chat::Say("hi".into())));
+        assert_eq!(parse_chat("/me waves"), Some(Chat::Emote("waves".into())));
+        assert_eq!(parse_chat("say \x1b[2Jgotcha"), Some(Chat::Say("[2Jgotcha".into())));
+        assert_eq!(parse_chat("say  \u{7}  "), None);
+        assert_eq!(parse_chat("say"), None);
+        assert_eq!(parse_chat("hit me"), None);
+    }
+
+    #[test]
+    fn a_long_chat_line_is_cut_rather_than_refused() {
+        let long = format!("say {}", "a".repeat(MAX_CHAT_LEN + 50));
+        assert_eq!(parse_chat(&long), Some(Chat::Say("a".repeat(MAX_CHAT_LEN))));
+    }
+
+    #[test]
+    fn chat_is_rendered_apart_from_the_dealer_s_lines() {
+        assert_eq!(render_chat("Ada", &Chat::Say("gl".into())), "<Ada> gl\n");
+        assert_eq!(render_chat("Ada", &Chat::Emote("waves".into())), "* Ada waves\n");
+    }
+
+    #[test]
+    fn the_mode_is_switched_by_the_same_words_in_either_mode() {
+        assert_eq!(parse_mode("mode json"), Some(Mode::Json));
+        assert_eq!(parse_mode(" MODE Text "), Some(Mode::Text));
+        assert_eq!(parse_mode(r#"{"cmd":"mode","mode":"text"}"#), Some(Mode::Text));
+        assert_eq!(parse_mode("mode"), None);
+    }
+
+    #[test]
+    fn a_hint_names_the_hand_only_once_the_seat_has_split() {
+        assert_eq!(parse_play(" Hint ", Mode::Text), Some(Play::Hint));
+        let first = Line::Hint { hand: 0, action: Action::Double };
+        let second = Line::Hint { hand: 1, action: Action::Stand };
+        assert_eq!(render_line(&first, Mode::Text), "Basic strategy says double\n");
+        assert_eq!(render_line(&second, Mode::Text), "Basic strategy says stand on hand 2\n");
+    }
+
+    #[test]
+    fn a_grade_reads_as_moves_mistakes_and_chips_given_up() {
+        assert_eq!(parse_play("stats", Mode::Text), Some(Play::Stats));
+        let accuracy = Accuracy { decisions: 8, mistakes: 2, ev_lost: 1.234 };
+        assert_eq!(
+            render(&Event::Graded { name: "Ada".into(), accuracy }),
+            "Ada: 8 moves, 2 off basic strategy (25%), 1.23 chips of expected value given up\n"
+        );
+        assert!(render_line(&Line::Stats(accuracy), Mode::Text).starts_with("You: 8 moves"));
+    }
+
+    #[test]
+    fn a_count_is_answered_with_or_without_a_true_count_and_marked_on_both() {
+        assert_eq!(parse_play("QUIZ", Mode::Text), Some(Play::Quiz));
+        let answer = |running, true_count| Some(Play::Count { running, true_count });
+        assert_eq!(parse_play("count -3", Mode::Text), answer(-3, None));
+        assert_eq!(parse_play("count 7 2", Mode::Text), answer(7, Some(2)));
+        assert_eq!(parse_play("count seven", Mode::Text), None);
+        let mut score = Score::default();
+        let reading = Reading { running: 7, true_count: Some(2.69) };
+        assert_eq!(
+            render_line(&Line::CountChecked(score.mark(reading, 6, None)), Mode::Text),
+            "Running count 7 -- you said 6, wrong. True count 2.7 -- you said nothing, wrong. \
+             0 of 1 running counts right, 0 of 1 true counts\n"
+        );
+        let ko = Reading { running: -3, true_count: None };
+        assert_eq!(
+            render_line(&Line::CountChecked(score.mark(ko, -3, None)), Mode::Text),
+            "Running count -3 -- you said -3, right. 1 of 2 running counts right\n"
+        );
+        assert_eq!(
+            render(&Event::CountAsked { true_count: false }),
+            "Count check: the running count? Answer count <running>\n"
+        );
+    }
+
+    #[test]
+    fn text_mode_reads_any_line_as_a_name_and_json_mode_does_not() {
+        assert_eq!(parse_login("Ada", Mode::Text), Some(Login::Name("Ada".into())));
+        assert_eq!(parse_login("resume 1-ab", Mode::Text), Some(Login::Resume("1-ab".into())));
+        assert_eq!(parse_login("Ada", Mode::Json), None);
+        assert_eq!(parse_play("say hi", Mode::Text), Some(Play::Chat(Chat::Say("hi".into()))));
+        assert_eq!(parse_play("hit", Mode::Text), Some(Play::Command(Command::Hit)));
+        assert_eq!(parse_play("hit", Mode::Json), None);
+    }
+
+    #[test]
+    fn text_lines_read_as_they_did_before_there_was_a_json_mode() {
+        let refused = Line::Refused(Refusal::Table(TableError::BetTooSmall { min_bet: 5 }));
+        assert_eq!(render_line(&refused, Mode::Text), "the minimum bet is 5\n");
+        let taken = Line::Refused(Refusal::Ledger(LedgerError::InUse));
+        assert_eq!(
+            render_line(&taken, Mode::Text),
+            "that name is already playing. What is your name?\n"
+        );
+        assert_eq!(render_line(&Line::Unrecognised(Stage::Lobby), Mode::Text), LOBBY_HELP);
+        assert!(render_line(&Line::Prompt(Stage::Table), Mode::Json).starts_with('{'));
+    }
+
     #[test]
     fn a_bet_may_be_written_with_or_without_the_verb() {
         assert_eq!(parse_command("bet 25"), Some(Command::Bet(25)));
@@ -96,6 +651,46 @@ mod tests {
         assert_eq!(parse_command("bet 5 5"), None);
     }
 
+    #[test]
+    fn the_lobby_lists_joins_and_creates_tables() {
+        assert_eq!(parse_lobby_command("LIST"), Some(LobbyCommand::List));
+        assert_eq!(parse_lobby_command("join 2"), Some(LobbyCommand::Join(2)));
+        assert_eq!(parse_lobby_command("join two"), None);
+        assert_eq!(parse_lobby_command("watch 3"), Some(LobbyCommand::Watch(3)));
+        assert_eq!(parse_lobby_command("create"), Some(LobbyCommand::Create(TableSpec::default())));
+        let high = TableSpec { min_bet: Some(25), dealer_hits_soft_17: Some(true) };
+        assert_eq!(parse_lobby_command("create 25 H17"), Some(LobbyCommand::Create(high)));
+        assert_eq!(parse_lobby_command("create 25 25"), None);
+        assert_eq!(parse_lobby_command("hit"), None);
+    }
+
+    #[test]
+    fn a_resume_token_is_taken_verbatim_in_place_of_a_name() {
+        assert_eq!(parse_resume("resume 2-00ab"), Some("2-00ab".to_string()));
+        assert_eq!(parse_resume(" RESUME 2-00AB "), Some("2-00AB".to_string()));
+        assert_eq!(parse_resume("resume"), None);
+        assert_eq!(parse_resume("Ada"), None);
+    }
+
+    #[test]
+    fn a_returning_player_is_shown_their_balance() {
+        assert_eq!(render_account("Ada", 340), "Ada, you have 340 chips\n");
+    }
+
+    #[test]
+    fn a_listing_shows_the_stakes_the_soft_17_rule_and_the_seats_taken() {
+        let mut lobby = Lobby::new(house());
+        let spec = TableSpec { min_bet: Some(25), dealer_hits_soft_17: Some(true) };
+        let mut listing = lobby.open(&spec).expect("room on the floor");
+        listing.seated = 3;
+        assert_eq!(
+            render_listing(&listing),
+            "Table 1: 25 to 500, dealer hits soft 17, blackjack pays 3:2 -- 3 of 7 seats taken\n"
+        );
+        listing.watching = 2;
+        assert!(render_listing(&listing).ends_with("seats taken, 2 watching\n"));
+    }
+
     #[test]
     fn a_soft_total_is_labelled_soft_and_a_hard_one_is_not() {
         assert_eq!(score(Total { value: 17, soft: true }), "soft 17");
@@ -106,27 +701,72 @@ mod tests {
     fn a_dealt_hand_renders_cards_then_total() {
         let event = Event::Dealt {
             name: "Ada".into(),
+            hand: 0,
             cards: vec![card(Rank::Ace), card(Rank::King)],
             total: Total { value: 21, soft: true },
         };
         assert_eq!(render(&event), "Ada: A\u{2665} K\u{2665} (soft 21)\n");
     }
 
+    #[test]
+    fn hands_added_by_a_split_are_numbered_from_two() {
+        let total = Total { value: 18, soft: false };
+        let first = Event::Stood { name: "Ada".into(), hand: 0, total };
+        let second = Event::Stood { name: "Ada".into(), hand: 1, total };
+        assert_eq!(render(&first), "Ada stands on 18\n");
+        assert_eq!(render(&second), "Ada (hand 2) stands on 18\n");
+    }
+
     #[test]
     fn every_event_renders_a_non_empty_line() {
         let total = Total { value: 20, soft: false };
         let events = [
-            Event::Joined { name: "Ada".into(), bankroll: 100 },
+            Event::Joined { name: "Ada".into(), bankroll: 100, bot: false },
+            Event::Joined { name: "Bot 1".into(), bankroll: 100, bot: true },
             Event::Left { name: "Ada".into() },
-            Event::BettingOpened { min_bet: 5 },
+            Event::Watching { name: "Ada".into() },
+            Event::StoppedWatching { name: "Ada".into() },
+            Event::SteppedAway { name: "Ada".into() },
+            Event::Returned { name: "Ada".into(), bankroll: 100 },
+            Event::BettingOpened { min_bet: 5, max_bet: 500, natural_pays: Odds::THREE_TO_TWO },
             Event::BetPlaced { name: "Ada".into(), amount: 5 },
-            Event::DealerShows { card: card(Rank::Nine) },
-            Event::TurnStarted { name: "Ada".into() },
-            Event::Bust { name: "Ada".into(), total },
-            Event::Stood { name: "Ada".into(), total },
+            Event::DealerShows { card: card(Rank::Nine), hole_card: true },
+            Event::DealerShows { card: card(Rank::Nine), hole_card: false },
+            Event::InsuranceOffered,
+            Event::SurrenderOffered,
+            Event::SurrenderDeclined { name: "Ada".into() },
+            Event::EvenMoneyOffered { name: "Ada".into() },
+            Event::Insured { name: "Ada".into(), stake: 0 },
+            Event::Insured { name: "Ada".into(), stake: 5 },
+            Event::EvenMoneyTaken { name: "Ada".into() },
+            Event::DealerChecked,
+            Event::InsuranceSettled { name: "Ada".into(), stake: 5, payout: 15, bankroll: 110 },
+            Event::TurnStarted { name: "Ada".into(), hand: 0 },
+            Event::Doubled { name: "Ada".into(), hand: 0, stake: 10 },
+            Event::Split { name: "Ada".into(), hand: 0, stake: 5 },
+            Event::Surrendered { name: "Ada".into() },
+            Event::Bust { name: "Ada".into(), hand: 1, total },
+            Event::Stood { name: "Ada".into(), hand: 0, total },
             Event::DealerHand { cards: vec![card(Rank::Ten)], total },
-            Event::Settled { name: "Ada".into(), outcome: Outcome::Push, payout: 5, bankroll: 100 },
+            Event::Settled {
+                name: "Ada".into(),
+                hand: 0,
+                outcome: Outcome::Push,
+                payout: 5,
+                bankroll: 100,
+            },
             Event::OutOfChips { name: "Ada".into() },
+            Event::TurnTimedOut { name: "Ada".into() },
+            Event::SatOut { name: "Ada".into() },
+            Event::SatIn { name: "Ada".into() },
+            Event::SeatReleased { name: "Ada".into(), rounds: 5 },
+            Event::Graded {
+                name: "Ada".into(),
+                accuracy: Accuracy { decisions: 4, mistakes: 1, ev_lost: 0.5 },
+            },
+            Event::Shuffled,
+            Event::CountAsked { true_count: false },
+            Event::CountAsked { true_count: true },
         ];
         assert!(events.iter().all(|event| render(event).ends_with('\n')));
         assert!(events.iter().all(|event| render(event).len() > 1));
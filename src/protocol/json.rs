@@ -0,0 +1,654 @@
+//! The machine half of the protocol: one JSON object per line, each way.
+//!
+//! Every object the server sends carries `"v"`, the schema version, and a
+//! `"type"` saying which of the shapes below it is. Field names are part of
+//! the schema: a change that renames or removes one bumps [`SCHEMA_VERSION`],
+//! while adding a field or an event does not. Hands are counted from 0, as in
+//! [`Event`], and cards are `{"rank": "10", "suit": "hearts"}`.
+//!
+//! Clients send `{"cmd": ...}` objects; anything else is refused with an
+//! `unrecognised` error rather than guessed at.
+
+use super::{help, sanitize, Line, Login, Mode, Play, Refusal, Stage};
+use crate::card::{Card, Suit};
+use crate::chat::{Chat, MAX_CHAT_LEN};
+use crate::count::Verdict;
+use crate::hand::Total;
+use crate::ledger::LedgerError;
+use crate::lobby::{Listing, LobbyCommand, LobbyError, TableSpec};
+use crate::rules::Outcome;
+use crate::strategy::Accuracy;
+use crate::table::{Command, Event, TableError, MAX_SEATS};
+use serde_json::{json, Map, Value};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Renders one line as a single JSON object, newline included.
+pub fn render(line: &Line) -> String {
+    let (kind, fields) = match line {
+        Line::Prompt(stage) => ("prompt", json!({ "stage": stage_name(*stage) })),
+        Line::Unrecognised(stage) => ("error", unrecognised(*stage)),
+        Line::Mode(mode) => ("mode", json!({ "mode": mode_name(*mode) })),
+        Line::Account { name, bankroll } => {
+            ("account", json!({ "name": name, "bankroll": bankroll }))
+        }
+        Line::Listing(listing) => ("table", listing_fields(listing)),
+        Line::ResumeToken { token, grace_secs } => {
+            ("resume_token", json!({ "token": token, "grace_secs": grace_secs }))
+        }
+        Line::Event(event) => ("event", event_fields(event)),
+        Line::Chat { name, chat } => ("chat", chat_fields(name, chat)),
+        Line::Hint { hand, action } => {
+            ("hint", json!({ "hand": hand, "action": action.to_string() }))
+        }
+        Line::Stats(accuracy) => ("stats", accuracy_fields(accuracy)),
+        Line::CountChecked(verdict) => ("count_checked", verdict_fields(verdict)),
+        Line::Refused(refusal) => ("error", refusal_fields(refusal)),
+    };
+    let mut object = Map::new();
+    object.insert("v".into(), SCHEMA_VERSION.into());
+    object.insert("type".into(), kind.into());
+    if let Value::Object(fields) = fields {
+        object.extend(fields);
+    }
+    format!("{}\n", Value::Object(object))
+}
+
+fn stage_name(stage: Stage) -> &'static str {
+    match stage {
+        Stage::Name => "name",
+        Stage::Lobby => "lobby",
+        Stage::Table => "table",
+        Stage::Gallery => "gallery",
+    }
+}
+
+fn mode_name(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Text => "text",
+        Mode::Json => "json",
+    }
+}
+
+/// Carries the text help as its message, which names every command even
+/// though a JSON client spells them differently.
+fn unrecognised(stage: Stage) -> Value {
+    json!({
+        "code": "unrecognised",
+        "stage": stage_name(stage),
+        "message": help(stage).trim_end(),
+    })
+}
+
+fn listing_fields(listing: &Listing) -> Value {
+    let rules = &listing.rules;
+    json!({
+        "table": listing.id,
+        "min_bet": rules.min_bet,
+        "max_bet": rules.max_bet,
+        "dealer_hits_soft_17": rules.dealer_hits_soft_17,
+        "natural_pays": rules.natural_pays.to_string(),
+        "seated": listing.seated,
+        "seats": MAX_SEATS,
+        "watching": listing.watching,
+    })
+}
+
+fn chat_fields(name: &str, chat: &Chat) -> Value {
+    match chat {
+        Chat::Say(text) => json!({ "name": name, "text": text, "emote": false }),
+        Chat::Emote(text) => json!({ "name": name, "text": text, "emote": true }),
+    }
+}
+
+fn event_fields(event: &Event) -> Value {
+    let (name, mut fields) = match event {
+        Event::Joined { name, bankroll, bot } => {
+            ("joined", json!({ "name": name, "bankroll": bankroll, "bot": bot }))
+        }
+        Event::Left { name } => ("left", json!({ "name": name })),
+        Event::Watching { name } => ("watching", json!({ "name": name })),
+        Event::StoppedWatching { name } => ("stopped_watching", json!({ "name": name })),
+        Event::SteppedAway { name } => ("stepped_away", json!({ "name": name })),
+        Event::Returned { name, bankroll } => {
+            ("returned", json!({ "name": name, "bankroll": bankroll }))
+        }
+        Event::BettingOpened { min_bet, max_bet, natural_pays } => (
+            "betting_opened",
+            json!({
+                "min_bet": min_bet,
+                "max_bet": max_bet,
+                "natural_pays": natural_pays.to_string(),
+            }),
+        ),
+        Event::BetPlaced { name, amount } => {
+            ("bet_placed", json!({ "name": name, "amount": amount }))
+        }
+        Event::Dealt { name, hand, cards, total } => (
+            "dealt",
+            json!({
+                "name": name,
+                "hand": hand,
+                "cards": cards_value(cards),
+                "total": total_value(*total),
+            }),
+        ),
+        Event::DealerShows { card, hole_card } => {
+            ("dealer_shows", json!({ "card": card_value(*card), "hole_card": hole_card }))
+        }
+        Event::InsuranceOffered => ("insurance_offered", json!({})),
+        Event::SurrenderOffered => ("surrender_offered", json!({})),
+        Event::SurrenderDeclined { name } => ("surrender_declined", json!({ "name": name })),
+        Event::EvenMoneyOffered { name } => ("even_money_offered", json!({ "name": name })),
+        Event::Insured { name, stake } => ("insured", json!({ "name": name, "stake": stake })),
+        Event::EvenMoneyTaken { name } => ("even_money_taken", json!({ "name": name })),
+        Event::DealerChecked => ("dealer_checked", json!({})),
+        Event::InsuranceSettled { name, stake, payout, bankroll } => (
+            "insurance_settled",
+            json!({ "name": name, "stake": stake, "payout": payout, "bankroll": bankroll }),
+        ),
+        Event::TurnStarted { name, hand } => {
+            ("turn_started", json!({ "name": name, "hand": hand }))
+        }
+        Event::Doubled { name, hand, stake } => {
+            ("doubled", json!({ "name": name, "hand": hand, "stake": stake }))
+        }
+        Event::Split { name, hand, stake } => {
+            ("split", json!({ "name": name, "hand": hand, "stake": stake }))
+        }
+        Event::Surrendered { name } => ("surrendered", json!({ "name": name })),
+        Event::Bust { name, hand, total } => {
+            ("bust", json!({ "name": name, "hand": hand, "total": total_value(*total) }))
+        }
+        Event::Stood { name, hand, total } => {
+            ("stood", json!({ "name": name, "hand": hand, "total": total_value(*total) }))
+        }
+        Event::DealerHand { cards, total } => {
+            ("dealer_hand", json!({ "cards": cards_value(cards), "total": total_value(*total) }))
+        }
+        Event::Settled { name, hand, outcome, payout, bankroll } => (
+            "settled",
+            json!({
+                "name": name,
+                "hand": hand,
+                "outcome": outcome_name(*outcome),
+                "payout": payout,
+                "bankroll": bankroll,
+            }),
+        ),
+        Event::OutOfChips { name } => ("out_of_chips", json!({ "name": name })),
+        Event::TurnTimedOut { name } => ("turn_timed_out", json!({ "name": name })),
+        Event::SatOut { name } => ("sat_out", json!({ "name": name })),
+        Event::SatIn { name } => ("sat_in", json!({ "name": name })),
+        Event::SeatReleased { name, rounds } => {
+            ("seat_released", json!({ "name": name, "rounds": rounds }))
+        }
+        Event::Graded { name, accuracy } => {
+            let mut fields = accuracy_fields(accuracy);
+            fields["name"] = name.as_str().into();
+            ("graded", fields)
+        }
+        Event::Shuffled => ("shuffled", json!({})),
+        Event::CountAsked { true_count } => ("count_asked", json!({ "true_count": true_count })),
+    };
+    fields["event"] = name.into();
+    fields
+}
+
+/// `ev_lost` is in chips and fractional; the counts are whole.
+fn accuracy_fields(accuracy: &Accuracy) -> Value {
+    json!({
+        "decisions": accuracy.decisions,
+        "mistakes": accuracy.mistakes,
+        "ev_lost": accuracy.ev_lost,
+    })
+}
+
+/// The exact counts beside the answer given; `true_count` and `true_right`
+/// are null for a system without a true count.
+fn verdict_fields(verdict: &Verdict) -> Value {
+    json!({
+        "running": verdict.reading.running,
+        "true_count": verdict.reading.true_count,
+        "answer": { "running": verdict.running, "true_count": verdict.true_count },
+        "running_right": verdict.running_right,
+        "true_right": verdict.true_right,
+        "score": {
+            "asked": verdict.score.asked,
+            "running_right": verdict.score.running_right,
+            "true_right": verdict.score.true_right,
+        },
+    })
+}
+
+fn card_value(card: Card) -> Value {
+    let suit = match card.suit {
+        Suit::Spades => "spades",
+        Suit::Hearts => "hearts",
+        Suit::Diamonds => "diamonds",
+        Suit::Clubs => "clubs",
+    };
+    json!({ "rank": card.rank.to_string(), "suit": suit })
+}
+
+fn cards_value(cards: &[Card]) -> Value {
+    cards.iter().map(|&card| card_value(card)).collect()
+}
+
+fn total_value(total: Total) -> Value {
+    json!({ "value": total.value, "soft": total.soft })
+}
+
+fn outcome_name(outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::Blackjack => "blackjack",
+        Outcome::EvenMoney => "even_money",
+        Outcome::Win => "win",
+        Outcome::Push => "push",
+        Outcome::Surrender => "surrender",
+        Outcome::Lose => "lose",
+    }
+}
+
+/// Every error has a `code` to branch on and the text client's `message`;
+/// the numbers a message mentions are fields of their own.
+fn refusal_fields(refusal: &Refusal) -> Value {
+    let (code, mut fields) = match refusal {
+        Refusal::Table(error) => table_error(error),
+        Refusal::Lobby(error) => lobby_error(error),
+        Refusal::Ledger(LedgerError::InUse) => ("name_in_use", json!({})),
+        Refusal::Ledger(LedgerError::Reserved) => ("name_reserved", json!({})),
+        Refusal::UnknownToken => ("unknown_token", json!({})),
+        Refusal::ChattingTooFast => ("chatting_too_fast", json!({})),
+    };
+    fields["code"] = code.into();
+    fields["message"] = refusal.to_string().into();
+    fields
+}
+
+fn table_error(error: &TableError) -> (&'static str, Value) {
+    match error {
+        TableError::UnknownSeat => ("unknown_seat", json!({})),
+        TableError::AlreadySeated => ("already_seated", json!({})),
+        TableError::TableFull => ("table_full", json!({ "seats": MAX_SEATS })),
+        TableError::WrongPhase => ("wrong_phase", json!({})),
+        TableError::AlreadyBet => ("already_bet", json!({})),
+        TableError::NotYourTurn => ("not_your_turn", json!({})),
+        TableError::BetTooSmall { min_bet } => ("bet_too_small", json!({ "min_bet": min_bet })),
+        TableError::BetTooLarge { max_bet } => ("bet_too_large", json!({ "max_bet": max_bet })),
+        TableError::InsufficientChips { bankroll } => {
+            ("insufficient_chips", json!({ "bankroll": bankroll }))
+        }
+        TableError::NotOnTwoCards => ("not_on_two_cards", json!({})),
+        TableError::CannotAffordDouble { bankroll } => {
+            ("cannot_afford_double", json!({ "bankroll": bankroll }))
+        }
+        TableError::NotAPair => ("not_a_pair", json!({})),
+        TableError::SplitLimit { hands } => ("split_limit", json!({ "hands": hands })),
+        TableError::CannotAffordSplit { bankroll } => {
+            ("cannot_afford_split", json!({ "bankroll": bankroll }))
+        }
+        TableError::InsuranceDecided => ("insurance_decided", json!({})),
+        TableError::InsuranceTooLarge { max } => ("insurance_too_large", json!({ "max": max })),
+        TableError::NoNatural => ("no_natural", json!({})),
+        TableError::NoInsuranceOffered => ("no_insurance_offered", json!({})),
+        TableError::SurrenderNotOffered => ("surrender_not_offered", json!({})),
+        TableError::TooLateToSurrender => ("too_late_to_surrender", json!({})),
+        TableError::NaturalNotSurrendered => ("natural_not_surrendered", json!({})),
+        TableError::ShoeExhausted => ("shoe_exhausted", json!({})),
+        TableError::AlreadySittingOut => ("already_sitting_out", json!({})),
+        TableError::NotSittingOut => ("not_sitting_out", json!({})),
+        TableError::GalleryFull => ("gallery_full", json!({})),
+        TableError::NotATrainer => ("not_a_trainer", json!({})),
+        TableError::NothingAsked => ("nothing_asked", json!({})),
+    }
+}
+
+fn lobby_error(error: &LobbyError) -> (&'static str, Value) {
+    match error {
+        LobbyError::NoSuchTable(id) => ("no_such_table", json!({ "table": id })),
+        LobbyError::TableFull(id) => ("table_full", json!({ "table": id, "seats": MAX_SEATS })),
+        LobbyError::GalleryFull(id) => ("gallery_full", json!({ "table": id })),
+        LobbyError::TooManyTables => ("too_many_tables", json!({})),
+        LobbyError::StakesOutOfRange { highest } => {
+            ("stakes_out_of_range", json!({ "highest": highest }))
+        }
+        LobbyError::CannotCover { min_bet, bankroll } => {
+            ("cannot_cover", json!({ "min_bet": min_bet, "bankroll": bankroll }))
+        }
+    }
+}
+
+/// `{"cmd": "mode", "mode": "json"}`, the JSON spelling of `mode json`.
+pub fn parse_mode(line: &str) -> Option<Mode> {
+    let (cmd, request) = parse(line)?;
+    match (cmd.as_str(), text(&request, "mode")?) {
+        ("mode", "json") => Some(Mode::Json),
+        ("mode", "text") => Some(Mode::Text),
+        _ => None,
+    }
+}
+
+/// `{"cmd": "login", "name": "Ada"}` or `{"cmd": "resume", "token": "..."}`.
+pub fn parse_login(line: &str) -> Option<Login> {
+    let (cmd, request) = parse(line)?;
+    match cmd.as_str() {
+        "login" => text(&request, "name").map(|name| Login::Name(name.to_string())),
+        "resume" => text(&request, "token").map(|token| Login::Resume(token.to_string())),
+        _ => None,
+    }
+}
+
+/// `list`, `join` and `watch` with a `table`, or `create` with an optional
+/// `min_bet` and `dealer_hits_soft_17`.
+pub fn parse_lobby_command(line: &str) -> Option<LobbyCommand> {
+    let (cmd, request) = parse(line)?;
+    match cmd.as_str() {
+        "list" => Some(LobbyCommand::List),
+        "join" => number(&request, "table").map(LobbyCommand::Join),
+        "watch" => number(&request, "table").map(LobbyCommand::Watch),
+        "create" => {
+            let min_bet = optional(&request, "min_bet", |value| value.as_u64()?.try_into().ok())?;
+            let dealer_hits_soft_17 = optional(&request, "dealer_hits_soft_17", Value::as_bool)?;
+            Some(LobbyCommand::Create(TableSpec { min_bet, dealer_hits_soft_17 }))
+        }
+        _ => None,
+    }
+}
+
+/// The table commands by their text names, with `sit_out` and `sit_in` for
+/// the two-word ones. `no` declines whatever is offered before the peek, as
+/// in text, and chat is `say` or `me` with a `text`.
+pub fn parse_play(line: &str) -> Option<Play> {
+    let (cmd, request) = parse(line)?;
+    let command = match cmd.as_str() {
+        "bet" => Command::Bet(number(&request, "amount")?),
+        "hit" => Command::Hit,
+        "stand" => Command::Stand,
+        "double" => Command::Double,
+        "split" => Command::Split,
+        "insure" => Command::Insure(number(&request, "stake")?),
+        "even_money" => Command::EvenMoney,
+        "surrender" => Command::Surrender,
+        "no" => Command::Decline,
+        "sit_out" => Command::SitOut,
+        "sit_in" => Command::SitIn,
+        "sit" => Command::Sit,
+        "say" => return chat(&request).map(|text| Play::Chat(Chat::Say(text))),
+        "me" => return chat(&request).map(|text| Play::Chat(Chat::Emote(text))),
+        "hint" => return Some(Play::Hint),
+        "stats" => return Some(Play::Stats),
+        "quiz" => return Some(Play::Quiz),
+        "count" => {
+            let running = signed(&request, "running")?;
+            let true_count = match request.get("true_count") {
+                None | Some(Value::Null) => None,
+                Some(_) => Some(signed(&request, "true_count")?),
+            };
+            return Some(Play::Count { running, true_count });
+        }
+        _ => return None,
+    };
+    Some(Play::Command(command))
+}
+
+fn parse(line: &str) -> Option<(String, Map<String, Value>)> {
+    let Value::Object(mut request) = serde_json::from_str(line).ok()? else {
+        return None;
+    };
+    match request.remove("cmd")? {
+        Value::String(cmd) => Some((cmd, request)),
+        _ => None,
+    }
+}
+
+fn text<'a>(request: &'a Map<String, Value>, field: &str) -> Option<&'a str> {
+    request.get(field)?.as_str()
+}
+
+fn number<T: TryFrom<u64>>(request: &Map<String, Value>, field: &str) -> Option<T> {
+    request.get(field)?.as_u64()?.try_into().ok()
+}
+
+fn signed(request: &Map<String, Value>, field: &str) -> Option<i32> {
+    request.get(field)?.as_i64()?.try_into().ok()
+}
+
+/// Chat over JSON is held to the same rules as typed chat.
+fn chat(request: &Map<String, Value>) -> Option<String> {
+    let said = sanitize(text(request, "text")?, MAX_CHAT_LEN);
+    (!said.is_empty()).then_some(said)
+}
+
+/// A missing or null field is `Some(None)`; one of the wrong type is `None`,
+/// so that a typo is refused rather than quietly ignored.
+fn optional<T>(
+    request: &Map<String, Value>,
+    field: &str,
+    read: impl Fn(&Value) -> Option<T>,
+) -> Option<Option<T>> {
+    match request.get(field) {
+        None | Some(Value::Null) => Some(None),
+        Some(value) => read(value).map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Rank;
+    use crate::count::{Reading, Score};
+    use crate::lobby::Lobby;
+    use crate::rules::fixtures::house;
+    use crate::strategy::{Accuracy, Action};
+
+    fn object(line: &Line) -> Value {
+        let rendered = render(line);
+        assert!(rendered.ends_with('\n') && !rendered.trim_end().contains('\n'));
+        serde_json::from_str(&rendered).expect("valid JSON")
+    }
+
+    #[test]
+    fn every_line_carries_the_schema_version_and_its_type() {
+        let rendered = object(&Line::Event(Event::InsuranceOffered));
+        assert_eq!(rendered, json!({ "v": 1, "type": "event", "event": "insurance_offered" }));
+        let prompt = object(&Line::Prompt(Stage::Lobby));
+        assert_eq!(prompt, json!({ "v": 1, "type": "prompt", "stage": "lobby" }));
+    }
+
+    #[test]
+    fn a_bot_says_so_as_it_sits_down_and_its_name_is_refused_to_players() {
+        let bot = Event::Joined { name: "Bot 2".into(), bankroll: 200, bot: true };
+        let expected = json!({
+            "v": 1,
+            "type": "event",
+            "event": "joined",
+            "name": "Bot 2",
+            "bankroll": 200,
+            "bot": true,
+        });
+        assert_eq!(object(&Line::Event(bot)), expected);
+        let reserved = object(&Line::Refused(Refusal::Ledger(LedgerError::Reserved)));
+        assert_eq!(reserved["code"], "name_reserved");
+    }
+
+    #[test]
+    fn a_hint_is_asked_for_and_answered_with_the_action_s_command_name() {
+        assert_eq!(parse_play(r#"{"cmd":"hint"}"#), Some(Play::Hint));
+        let hint = object(&Line::Hint { hand: 1, action: Action::Surrender });
+        assert_eq!(hint, json!({ "v": 1, "type": "hint", "hand": 1, "action": "surrender" }));
+    }
+
+    #[test]
+    fn a_count_answer_is_signed_and_its_marks_come_back_beside_the_exact_count() {
+        assert_eq!(parse_play(r#"{"cmd":"quiz"}"#), Some(Play::Quiz));
+        let answer = |running, true_count| Some(Play::Count { running, true_count });
+        assert_eq!(parse_play(r#"{"cmd":"count","running":-4}"#), answer(-4, None));
+        assert_eq!(
+            parse_play(r#"{"cmd":"count","running":5,"true_count":-1}"#),
+            answer(5, Some(-1))
+        );
+        assert_eq!(parse_play(r#"{"cmd":"count","running":"5"}"#), None);
+        assert_eq!(parse_play(r#"{"cmd":"count","running":1,"true_count":1.5}"#), None);
+        let verdict =
+            Score::default().mark(Reading { running: 5, true_count: Some(2.5) }, 5, Some(3));
+        assert_eq!(
+            object(&Line::CountChecked(verdict)),
+            json!({
+                "v": 1,
+                "type": "count_checked",
+                "running": 5,
+                "true_count": 2.5,
+                "answer": { "running": 5, "true_count": 3 },
+                "running_right": true,
+                "true_right": true,
+                "score": { "asked": 1, "running_right": 1, "true_right": 1 },
+            })
+        );
+        let asked = object(&Line::Event(Event::CountAsked { true_count: true }));
+        assert_eq!(
+            asked,
+            json!({ "v": 1, "type": "event", "event": "count_asked", "true_count": true })
+        );
+    }
+
+    #[test]
+    fn a_grade_keeps_its_fractional_chips() {
+        assert_eq!(parse_play(r#"{"cmd":"stats"}"#), Some(Play::Stats));
+        let accuracy = Accuracy { decisions: 8, mistakes: 2, ev_lost: 1.25 };
+        assert_eq!(
+            object(&Line::Event(Event::Graded { name: "Ada".into(), accuracy })),
+            json!({
+                "v": 1,
+                "type": "event",
+                "event": "graded",
+                "name": "Ada",
+                "decisions": 8,
+                "mistakes": 2,
+                "ev_lost": 1.25,
+            })
+        );
+        let stats = object(&Line::Stats(accuracy));
+        assert_eq!(
+            stats,
+            json!({ "v": 1, "type": "stats", "decisions": 8, "mistakes": 2, "ev_lost": 1.25 })
+        );
+    }
+
+    #[test]
+    fn cards_and_totals_are_structured_rather_than_printed() {
+        let event = Event::Dealt {
+            name: "Ada".into(),
+            hand: 1,
+            cards: vec![
+                Card { rank: Rank::Ace, suit: Suit::Spades },
+                Card { rank: Rank::Ten, suit: Suit::Hearts },
+            ],
+            total: Total { value: 21, soft: true },
+        };
+        assert_eq!(
+            object(&Line::Event(event)),
+            json!({
+                "v": 1,
+                "type": "event",
+                "event": "dealt",
+                "name": "Ada",
+                "hand": 1,
+                "cards": [{ "rank": "A", "suit": "spades" }, { "rank": "10", "suit": "hearts" }],
+                "total": { "value": 21, "soft": true },
+            })
+        );
+    }
+
+    #[test]
+    fn a_settlement_names_its_outcome() {
+        let event = Event::Settled {
+            name: "Ada".into(),
+            hand: 0,
+            outcome: Outcome::EvenMoney,
+            payout: 20,
+            bankroll: 120,
+        };
+        let rendered = object(&Line::Event(event));
+        assert_eq!(rendered["outcome"], "even_money");
+        assert_eq!(rendered["bankroll"], 120);
+    }
+
+    #[test]
+    fn an_error_has_a_code_a_message_and_its_numbers() {
+        let refusal = Refusal::Table(TableError::BetTooSmall { min_bet: 5 });
+        assert_eq!(
+            object(&Line::Refused(refusal)),
+            json!({
+                "v": 1,
+                "type": "error",
+                "code": "bet_too_small",
+                "message": "the minimum bet is 5",
+                "min_bet": 5,
+            })
+        );
+        let taken = object(&Line::Refused(Refusal::Ledger(LedgerError::InUse)));
+        assert_eq!(taken["code"], "name_in_use");
+        assert_eq!(object(&Line::Unrecognised(Stage::Table))["code"], "unrecognised");
+    }
+
+    #[test]
+    fn a_listing_spells_out_the_rules() {
+        let mut lobby = Lobby::new(house());
+        let listing = lobby.open(&TableSpec::default()).expect("room on the floor");
+        let rendered = object(&Line::Listing(listing));
+        assert_eq!(rendered["type"], "table");
+        assert_eq!(rendered["table"], 1);
+        assert_eq!(rendered["natural_pays"], "3:2");
+        assert_eq!(rendered["seats"], MAX_SEATS);
+    }
+
+    #[test]
+    fn commands_are_objects_named_by_cmd() {
+        let play = |line: &str| parse_play(line);
+        assert_eq!(play(r#"{"cmd":"bet","amount":25}"#), Some(Play::Command(Command::Bet(25))));
+        assert_eq!(play(r#"{"cmd":"hit"}"#), Some(Play::Command(Command::Hit)));
+        assert_eq!(play(r#"{"cmd":"insure","stake":0}"#), Some(Play::Command(Command::Insure(0))));
+        assert_eq!(play(r#"{"cmd":"no"}"#), Some(Play::Command(Command::Decline)));
+        assert_eq!(play(r#"{"cmd":"sit_out"}"#), Some(Play::Command(Command::SitOut)));
+        assert_eq!(
+            play(r#"{"cmd":"me","text":" waves\u0007"}"#),
+            Some(Play::Chat(Chat::Emote("waves".into())))
+        );
+        assert_eq!(play(r#"{"cmd":"bet","amount":-5}"#), None);
+        assert_eq!(play(r#"{"cmd":"bet","amount":"25"}"#), None);
+        assert_eq!(play(r#"{"cmd":"say","text":"  "}"#), None);
+        assert_eq!(play(r#"{"cmd":"fold"}"#), None);
+        assert_eq!(play("hit"), None);
+    }
+
+    #[test]
+    fn the_lobby_and_login_take_their_arguments_as_fields() {
+        assert_eq!(parse_login(r#"{"cmd":"login","name":"Ada"}"#), Some(Login::Name("Ada".into())));
+        assert_eq!(
+            parse_login(r#"{"cmd":"resume","token":"1-ab"}"#),
+            Some(Login::Resume("1-ab".into()))
+        );
+        assert_eq!(parse_login(r#"{"name":"Ada"}"#), None);
+        assert_eq!(parse_lobby_command(r#"{"cmd":"join","table":2}"#), Some(LobbyCommand::Join(2)));
+        let high = TableSpec { min_bet: Some(25), dealer_hits_soft_17: Some(true) };
+        let create = r#"{"cmd":"create","min_bet":25,"dealer_hits_soft_17":true}"#;
+        assert_eq!(parse_lobby_command(create), Some(LobbyCommand::Create(high)));
+        let house_rules = r#"{"cmd":"create","min_bet":null}"#;
+        assert_eq!(
+            parse_lobby_command(house_rules),
+            Some(LobbyCommand::Create(TableSpec::default()))
+        );
+        assert_eq!(parse_lobby_command(r#"{"cmd":"create","dealer_hits_soft_17":"yes"}"#), None);
+    }
+
+    #[test]
+    fn the_mode_can_be_switched_back_in_json() {
+        assert_eq!(parse_mode(r#"{"cmd":"mode","mode":"text"}"#), Some(Mode::Text));
+        assert_eq!(parse_mode(r#"{"cmd":"mode","mode":"xml"}"#), None);
+        assert_eq!(
+            object(&Line::Mode(Mode::Json)),
+            json!({ "v": 1, "type": "mode", "mode": "json" })
+        );
+    }
+}
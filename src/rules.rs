@@ -1,6 +1,8 @@
 //! Table rules: the dealer's drawing policy, hand comparison, and payouts.
 
 use crate::hand::Hand;
+use std::fmt;
+use std::str::FromStr;
 
 /// Everything about how a table plays, independent of how it is served.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -8,18 +10,134 @@ pub struct Rules {
     pub packs: u8,
     pub dealer_hits_soft_17: bool,
     pub min_bet: u32,
+    pub max_bet: u32,
     pub starting_bankroll: u32,
     pub min_players: usize,
+    /// Rounds in a row a seat may sit out before it is given up, so that
+    /// nobody can hold a seat they are not playing. 0 never gives it up.
+    pub max_rounds_sat_out: u32,
+    /// Splits allowed on a hand that is itself half of a split: 0 stops a
+    /// seat at two hands, 3 lets it reach four.
+    pub max_resplits: u8,
+    /// Split aces take one card each and are closed, the usual casino rule.
+    pub split_aces_one_card: bool,
+    /// Whether an ace and a ten after splitting aces is paid as a natural.
+    pub split_ace_21_is_natural: bool,
+    pub surrender: Surrender,
+    pub hole_card: HoleCard,
+    /// European tables only: a dealer natural takes just the opening wager,
+    /// handing back whatever was added by doubling or splitting.
+    pub original_bets_only: bool,
+    pub natural_pays: Odds,
+}
+
+/// A payout ratio as tables print it: 3:2 wins 3 chips for every 2 staked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Odds {
+    pub win: u32,
+    pub per: u32,
+}
+
+impl Odds {
+    pub const THREE_TO_TWO: Self = Self { win: 3, per: 2 };
+
+    /// Winnings on `bet`, stake not included, in whole chips rounded down.
+    /// Widened on the way through so a large wager cannot overflow; past what
+    /// [`Odds::pays_up_to`] allows, it stops at `u32::MAX` rather than wrap.
+    pub fn winnings(self, bet: u32) -> u32 {
+        let winnings = u64::from(bet) * u64::from(self.win) / u64::from(self.per);
+        u32::try_from(winnings).unwrap_or(u32::MAX)
+    }
+
+    /// Whether a natural on any wager up to `max_bet` can be paid back, stake
+    /// and all, in a `u32` of chips. The config refuses a ratio that cannot.
+    pub fn pays_up_to(self, max_bet: u32) -> bool {
+        let winnings = u64::from(max_bet) * u64::from(self.win) / u64::from(self.per.max(1));
+        u64::from(max_bet) + winnings <= u64::from(u32::MAX)
+    }
+}
+
+impl FromStr for Odds {
+    type Err = ();
+
+    fn from_str(raw: &str) -> Result<Self, ()> {
+        let (win, per) = raw.split_once(':').ok_or(())?;
+        let win = win.trim().parse().map_err(|_| ())?;
+        let per = per.trim().parse().map_err(|_| ())?;
+        Ok(Self { win, per })
+    }
+}
+
+impl fmt::Display for Odds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.win, self.per)
+    }
+}
+
+/// When the dealer's second card is dealt, and so when a dealer natural is
+/// found out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HoleCard {
+    /// American: dealt face down with the up card and checked under an ace or
+    /// a ten, so a dealer natural ends the round before anyone acts.
+    Peek,
+    /// European: not dealt until every player has acted, so a dealer natural
+    /// is only found out after players may have doubled or split into it.
+    European,
+}
+
+impl FromStr for HoleCard {
+    type Err = ();
+
+    fn from_str(raw: &str) -> Result<Self, ()> {
+        match raw.to_lowercase().as_str() {
+            "peek" => Ok(Self::Peek),
+            "european" => Ok(Self::European),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Giving up half the wager to leave a hand on its first two cards. The two
+/// casino variants differ only in whether the dealer has checked for a natural
+/// first: early surrender still gets half back against a dealer blackjack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Surrender {
+    None,
+    Late,
+    Early,
+}
+
+impl FromStr for Surrender {
+    type Err = ();
+
+    fn from_str(raw: &str) -> Result<Self, ()> {
+        match raw.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "late" => Ok(Self::Late),
+            "early" => Ok(Self::Early),
+            _ => Err(()),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Outcome {
     Blackjack,
+    /// A natural that took 1:1 against a dealer ace rather than risk a push.
+    EvenMoney,
     Win,
     Push,
+    Surrender,
     Lose,
 }
 
+impl Outcome {
+    /// In declaration order, so `outcome as usize` indexes it.
+    pub const ALL: [Self; 6] =
+        [Self::Blackjack, Self::EvenMoney, Self::Win, Self::Push, Self::Surrender, Self::Lose];
+}
+
 /// The dealer has no choices: draw below 17, stand at 17 or better. The only
 /// house variation is soft 17 (H17 costs the player roughly 0.2% of edge),
 /// so it is the one knob exposed here.
@@ -54,6 +172,16 @@ fn resolve_naturals(player: &Hand, dealer: &Hand) -> Option<Outcome> {
     }
 }
 
+/// A surrendered hand gets half its wager back, except that late surrender is
+/// only offered once the dealer has checked for a natural — a dealer
+/// blackjack that was never checked for takes the whole wager.
+pub fn resolve_surrender(dealer: &Hand, rule: Surrender) -> Outcome {
+    if dealer.is_blackjack() && rule != Surrender::Early {
+        return Outcome::Lose;
+    }
+    Outcome::Surrender
+}
+
 fn compare_totals(player: &Hand, dealer: &Hand) -> Outcome {
     match player.total().value.cmp(&dealer.total().value) {
         std::cmp::Ordering::Greater => Outcome::Win,
@@ -63,19 +191,59 @@ fn compare_totals(player: &Hand, dealer: &Hand) -> Outcome {
 }
 
 /// Chips returned to the player, wager included, given that the wager was
-/// already taken off the bankroll when it was placed.
+/// already taken off the bankroll when it was placed. `bet` is the whole stake
+/// on the hand, so a doubled hand is settled on twice its opening wager.
 ///
 /// Chips are whole units, so a 3:2 natural on an odd wager rounds down in the
-/// house's favour — the same reason casinos ask for even bets.
-pub fn payout(outcome: Outcome, bet: u32) -> u32 {
+/// house's favour — the same reason casinos ask for even bets. Any ratio whose
+/// second figure does not divide the wager rounds the same way. The config
+/// keeps every stake a table takes small enough that none of this overflows;
+/// past that, it stops at `u32::MAX` rather than wrap.
+pub fn payout(outcome: Outcome, bet: u32, natural_pays: Odds) -> u32 {
     match outcome {
-        Outcome::Blackjack => bet + bet * 3 / 2,
-        Outcome::Win => bet * 2,
+        Outcome::Blackjack => bet.saturating_add(natural_pays.winnings(bet)),
+        Outcome::EvenMoney | Outcome::Win => bet.saturating_mul(2),
         Outcome::Push => bet,
+        Outcome::Surrender => bet / 2,
         Outcome::Lose => 0,
     }
 }
 
+/// Insurance is a side bet on the hole card, settled apart from the hand it
+/// insures: 2:1 plus the stake back on a dealer natural, nothing otherwise.
+pub fn insurance_payout(stake: u32, dealer_blackjack: bool) -> u32 {
+    if dealer_blackjack {
+        stake.saturating_mul(3)
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use crate::rules::{HoleCard, Odds, Rules, Surrender};
+
+    /// The documented defaults from `.env.example`.
+    pub fn house() -> Rules {
+        Rules {
+            packs: 6,
+            dealer_hits_soft_17: false,
+            min_bet: 5,
+            max_bet: 500,
+            starting_bankroll: 200,
+            min_players: 1,
+            max_rounds_sat_out: 5,
+            max_resplits: 2,
+            split_aces_one_card: true,
+            split_ace_21_is_natural: false,
+            surrender: Surrender::None,
+            hole_card: HoleCard::Peek,
+            original_bets_only: false,
+            natural_pays: Odds::THREE_TO_TWO,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,15 +316,88 @@ mod tests {
 
     #[test]
     fn natural_pays_three_to_two_and_a_win_pays_even_money() {
-        assert_eq!(payout(Outcome::Blackjack, 10), 25);
-        assert_eq!(payout(Outcome::Win, 10), 20);
-        assert_eq!(payout(Outcome::Push, 10), 10);
-        assert_eq!(payout(Outcome::Lose, 10), 0);
+        assert_eq!(payout(Outcome::Blackjack, 10, Odds::THREE_TO_TWO), 25);
+        assert_eq!(payout(Outcome::Win, 10, Odds::THREE_TO_TWO), 20);
+        assert_eq!(payout(Outcome::Push, 10, Odds::THREE_TO_TWO), 10);
+        assert_eq!(payout(Outcome::Lose, 10, Odds::THREE_TO_TWO), 0);
+    }
+
+    #[test]
+    fn insurance_pays_two_to_one_only_against_a_dealer_natural() {
+        assert_eq!(insurance_payout(5, true), 15);
+        assert_eq!(insurance_payout(5, false), 0);
+        assert_eq!(payout(Outcome::EvenMoney, 10, Odds::THREE_TO_TWO), 20);
+    }
+
+    #[test]
+    fn surrender_returns_half_the_wager_rounded_down() {
+        assert_eq!(payout(Outcome::Surrender, 10, Odds::THREE_TO_TWO), 5);
+        assert_eq!(payout(Outcome::Surrender, 5, Odds::THREE_TO_TWO), 2);
+    }
+
+    #[test]
+    fn only_early_surrender_survives_a_dealer_natural() {
+        let natural = hand(&[Ace, King]);
+        assert_eq!(resolve_surrender(&natural, Surrender::Early), Outcome::Surrender);
+        assert_eq!(resolve_surrender(&natural, Surrender::Late), Outcome::Lose);
+        assert_eq!(resolve_surrender(&hand(&[Ten, Nine]), Surrender::Late), Outcome::Surrender);
+    }
+
+    #[test]
+    fn surrender_rules_parse_from_their_names() {
+        assert_eq!("late".parse(), Ok(Surrender::Late));
+        assert_eq!("EARLY".parse(), Ok(Surrender::Early));
+        assert_eq!("none".parse(), Ok(Surrender::None));
+        assert_eq!("sometimes".parse::<Surrender>(), Err(()));
     }
 
     #[test]
     fn natural_on_an_odd_wager_rounds_down() {
-        assert_eq!(payout(Outcome::Blackjack, 5), 12);
-        assert_eq!(payout(Outcome::Blackjack, 1), 2);
+        assert_eq!(payout(Outcome::Blackjack, 5, Odds::THREE_TO_TWO), 12);
+        assert_eq!(payout(Outcome::Blackjack, 1, Odds::THREE_TO_TWO), 2);
+    }
+
+    #[test]
+    fn six_to_five_pays_six_per_five_and_rounds_the_rest_down() {
+        let six_to_five = Odds { win: 6, per: 5 };
+        assert_eq!(payout(Outcome::Blackjack, 10, six_to_five), 22);
+        assert_eq!(payout(Outcome::Blackjack, 7, six_to_five), 15);
+        assert_eq!(payout(Outcome::Blackjack, 4, six_to_five), 8);
+    }
+
+    #[test]
+    fn whole_number_ratios_never_round() {
+        assert_eq!(payout(Outcome::Blackjack, 7, Odds { win: 1, per: 1 }), 14);
+        assert_eq!(payout(Outcome::Blackjack, 7, Odds { win: 2, per: 1 }), 21);
+    }
+
+    #[test]
+    fn the_natural_ratio_does_not_touch_any_other_outcome() {
+        let two_to_one = Odds { win: 2, per: 1 };
+        assert_eq!(payout(Outcome::Win, 10, two_to_one), 20);
+        assert_eq!(payout(Outcome::EvenMoney, 10, two_to_one), 20);
+    }
+
+    #[test]
+    fn a_huge_wager_at_a_generous_ratio_does_not_overflow() {
+        assert_eq!(Odds::THREE_TO_TWO.winnings(2_147_483_647), 3_221_225_470);
+    }
+
+    #[test]
+    fn the_most_generous_ratio_a_table_takes_pays_its_largest_natural_in_full() {
+        let win = (u32::MAX - 500) / 500;
+        let generous = Odds { win, per: 1 };
+        assert!(generous.pays_up_to(500));
+        assert_eq!(payout(Outcome::Blackjack, 500, generous), 500 + 500 * win);
+        assert!(!Odds { win: win + 1, per: 1 }.pays_up_to(500));
+        assert!(!Odds { win: u32::MAX, per: 1 }.pays_up_to(1));
+    }
+
+    #[test]
+    fn odds_read_and_print_as_win_colon_stake() {
+        assert_eq!("6:5".parse(), Ok(Odds { win: 6, per: 5 }));
+        assert_eq!("3 : 2".parse::<Odds>().map(|odds| odds.to_string()), Ok("3:2".into()));
+        assert!("3/2".parse::<Odds>().is_err());
+        assert!("3:".parse::<Odds>().is_err());
     }
 }
@@ -0,0 +1,304 @@
+//! Headless play for measuring the house edge.
+//!
+//! Nothing here deals or settles a card: one seat sits at a real [`Table`]
+//! over a seeded RNG and a [`Policy`] answers for it, so every round goes
+//! through the same `rules::resolve` and `rules::payout` a player's does and
+//! the edge measured is the edge the server's tables have.
+//!
+//! A long run is cut into shards of [`SHARD_ROUNDS`], each dealt from its own
+//! shoe seeded from the run's seed and the shard's place in it, and played on
+//! however many threads there are. Which thread plays a shard changes nothing
+//! about it, and reports add up in whole numbers, so a run's report is the
+//! same bit for bit on one core or sixty-four.
+
+use crate::policy::Policy;
+use crate::rules::{Outcome, Rules};
+use crate::table::{Command, Event, Phase, Table, TableError};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::panic;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+/// The simulated seat, by id and by name.
+pub const SEAT: &str = "sim";
+
+/// Rounds to a shard: enough that a fresh shoe per shard is noise, few enough
+/// that a billion rounds keep every core busy to the end.
+pub const SHARD_ROUNDS: u64 = 1 << 16;
+
+/// Half of what a bankroll can hold: no run that finishes could take it to
+/// either end, so the seat is never shown the door or overflowed.
+const BANKROLL: u32 = u32::MAX / 2;
+
+/// What a run of rounds came to. Every figure is a whole-number sum, so two
+/// reports over different rounds add up exactly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub rounds: u64,
+    /// Opening wagers, in chips. Doubling and splitting put more at risk but
+    /// are not counted here, as a house edge is quoted per initial bet.
+    pub wagered: u64,
+    /// What the seat won over the run, in chips: below zero when the house
+    /// is ahead.
+    pub net: i64,
+    /// Each round's net squared, summed, for the spread.
+    pub squares: u128,
+    /// Hands settled each way, in the order of [`Outcome::ALL`]. A split
+    /// round settles more than one.
+    pub hands: [u64; 6],
+}
+
+impl Report {
+    /// Adds another run's rounds to this one's. Every field is a count or a
+    /// sum, so the order reports are merged in never shows.
+    pub fn merge(&mut self, other: &Report) {
+        self.rounds += other.rounds;
+        self.wagered += other.wagered;
+        self.net += other.net;
+        self.squares += other.squares;
+        for (hands, more) in self.hands.iter_mut().zip(other.hands) {
+            *hands += more;
+        }
+    }
+
+    /// The house's take per chip of opening wager.
+    pub fn house_edge(&self) -> f64 {
+        -(self.net as f64) / self.wagered as f64
+    }
+
+    /// The standard deviation of one round's result, in opening wagers.
+    pub fn sd_per_round(&self) -> f64 {
+        let rounds = self.rounds as f64;
+        let mean = self.net as f64 / rounds;
+        let variance = (self.squares as f64 / rounds - mean * mean) * rounds / (rounds - 1.0);
+        variance.max(0.0).sqrt() * rounds / self.wagered as f64
+    }
+
+    /// Half the width of a confidence interval on the house edge, `z`
+    /// standard errors either side: 1.96 for 95%.
+    pub fn margin(&self, z: f64) -> f64 {
+        z * self.sd_per_round() / (self.rounds as f64).sqrt()
+    }
+
+    /// The share of settled hands that ended in `outcome`.
+    pub fn frequency(&self, outcome: Outcome) -> f64 {
+        let settled: u64 = self.hands.iter().sum();
+        self.hands[outcome as usize] as f64 / settled as f64
+    }
+}
+
+/// Plays `rounds` rounds at a table of `rules` dealt from `rng`, with
+/// `policy` in the only seat. The seat is alone, so the table is told to
+/// deal to one player whatever the rules say. Insurance and even money are
+/// always declined, as basic strategy declines them.
+///
+/// A table refusal — a policy wagering outside the limits, or making a move
+/// the turn did not offer — stops the run, since the rounds after it would
+/// not be the policy's.
+pub fn run<R: Rng>(
+    rules: Rules,
+    rng: R,
+    rounds: u64,
+    policy: &mut dyn Policy,
+) -> Result<Report, TableError> {
+    let rules = Rules { min_players: 1, ..rules };
+    let mut table = Table::new(rules, rng).ungraded();
+    table.join_with_bankroll(SEAT, SEAT, BANKROLL)?;
+    let mut report = Report::default();
+    for _ in 0..rounds {
+        play_round(&mut table, &rules, policy, &mut report)?;
+    }
+    Ok(report)
+}
+
+/// Plays `rounds` rounds as shards on `threads` threads, each thread with a
+/// policy of its own from `policy` and each shard with a shoe from
+/// [`shard_rng`], whose first draw also reseeds the policy. The report is
+/// the same for any number of threads, as long as the policy keeps the
+/// promise on [`Policy`].
+///
+/// If any shard is refused a move, the refusal from the earliest such shard
+/// is returned, so an error does not depend on the threads either.
+pub fn run_sharded(
+    rules: Rules,
+    seed: u64,
+    rounds: u64,
+    threads: usize,
+    policy: impl Fn() -> Box<dyn Policy> + Sync,
+) -> Result<Report, TableError> {
+    sharded(rules, seed, rounds, SHARD_ROUNDS, threads, &policy)
+}
+
+/// The shoe for shard `shard` of a run seeded with `seed`. Neighbouring
+/// seeds and shards are scattered by a SplitMix64 step first, so no two
+/// shards of any two runs are dealt from related streams.
+pub fn shard_rng(seed: u64, shard: u64) -> StdRng {
+    let mut mixed = seed ^ shard.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    StdRng::seed_from_u64(mixed ^ (mixed >> 31))
+}
+
+/// Threads take the next unplayed shard until none are left, so a slow
+/// shard never leaves the others idle. A refused shard ends only itself:
+/// stopping the rest would make which shards were played, and so which
+/// refusal is reported, depend on the threads.
+fn sharded(
+    rules: Rules,
+    seed: u64,
+    rounds: u64,
+    shard_rounds: u64,
+    threads: usize,
+    policy: &(dyn Fn() -> Box<dyn Policy> + Sync),
+) -> Result<Report, TableError> {
+    let shards = (rounds + shard_rounds - 1) / shard_rounds;
+    let next = AtomicU64::new(0);
+    let play = || {
+        let mut player = policy();
+        let mut played = Report::default();
+        let mut refused: Option<(u64, TableError)> = None;
+        loop {
+            let shard = next.fetch_add(1, Ordering::Relaxed);
+            if shard >= shards {
+                return (played, refused);
+            }
+            let length = shard_rounds.min(rounds - shard * shard_rounds);
+            player.reseed(shard_rng(seed, shard).gen());
+            match run(rules, shard_rng(seed, shard), length, player.as_mut()) {
+                Ok(report) => played.merge(&report),
+                Err(error) => refused = refused.or(Some((shard, error))),
+            }
+        }
+    };
+    let finished: Vec<_> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1)).map(|_| scope.spawn(play)).collect();
+        let joined = workers.into_iter().map(|worker| worker.join());
+        joined.map(|result| result.unwrap_or_else(|panic| panic::resume_unwind(panic))).collect()
+    });
+    let refused = finished.iter().filter_map(|(_, refused)| *refused);
+    if let Some((_, error)) = refused.min_by_key(|&(shard, _)| shard) {
+        return Err(error);
+    }
+    let mut total = Report::default();
+    finished.iter().for_each(|(played, _)| total.merge(played));
+    Ok(total)
+}
+
+fn play_round<R: Rng>(
+    table: &mut Table<R>,
+    rules: &Rules,
+    policy: &mut dyn Policy,
+    report: &mut Report,
+) -> Result<(), TableError> {
+    let before = table.bankroll(SEAT).ok_or(TableError::UnknownSeat)?;
+    let wager = policy.wager(rules);
+    let mut events = table.apply(SEAT, Command::Bet(wager))?;
+    loop {
+        let command = match table.phase() {
+            Phase::Insurance => Command::Decline,
+            Phase::PlayerTurns => policy.play(&table.turn(SEAT)?, rules).into(),
+            _ => break,
+        };
+        events.extend(table.apply(SEAT, command)?);
+    }
+    let after = table.bankroll(SEAT).ok_or(TableError::UnknownSeat)?;
+    let net = i64::from(after) - i64::from(before);
+    report.rounds += 1;
+    report.wagered += u64::from(wager);
+    report.net += net;
+    report.squares += u128::from(net.unsigned_abs()).pow(2);
+    for event in &events {
+        if let Event::Settled { outcome, .. } = event {
+            report.hands[*outcome as usize] += 1;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{named, AlwaysStand, BasicStrategy, MimicDealer, RandomPlay};
+    use crate::rules::fixtures::house;
+    use crate::strategy::Action;
+    use crate::table::Turn;
+
+    fn play(seed: u64, rounds: u64, policy: &mut dyn Policy) -> Report {
+        let rules = Rules { min_players: 3, ..house() };
+        run(rules, StdRng::seed_from_u64(seed), rounds, policy).expect("every move is offered")
+    }
+
+    #[test]
+    fn the_same_seed_plays_the_same_rounds_and_another_does_not() {
+        let first = play(7, 2_000, &mut BasicStrategy::default());
+        assert_eq!(play(7, 2_000, named("basic").expect("known").as_mut()), first);
+        assert_ne!(play(8, 2_000, &mut BasicStrategy::default()), first);
+    }
+
+    #[test]
+    fn every_round_is_counted_once_at_the_wager_the_policy_placed() {
+        let report = play(1, 1_000, &mut MimicDealer);
+        assert_eq!((report.rounds, report.wagered), (1_000, 6_000), "5 is rounded up to pay 3:2");
+        let settled: u64 = report.hands.iter().sum();
+        assert!(settled >= 1_000, "a split round settles two hands");
+        assert_eq!(report.hands[Outcome::Surrender as usize], 0);
+    }
+
+    #[test]
+    fn basic_strategy_beats_mimicking_the_dealer_which_beats_never_drawing() {
+        let basic = play(3, 20_000, &mut BasicStrategy::default()).house_edge();
+        let dealer = play(3, 20_000, &mut MimicDealer).house_edge();
+        let stand = play(3, 20_000, &mut AlwaysStand).house_edge();
+        assert!(basic < 0.03 && 0.03 < dealer && dealer < 0.10 && 0.10 < stand);
+    }
+
+    #[test]
+    fn a_sharded_run_reports_the_same_on_any_number_of_threads() {
+        let dealer = || -> Box<dyn Policy> { Box::new(MimicDealer) };
+        let one = sharded(house(), 9, 10_000, 1_500, 1, &dealer).expect("all offered");
+        for threads in [2, 3, 8] {
+            assert_eq!(sharded(house(), 9, 10_000, 1_500, threads, &dealer), Ok(one));
+        }
+        assert_eq!(one.rounds, 10_000);
+
+        // Each thread's dice start anywhere; each shard's start from its seed.
+        let random = || -> Box<dyn Policy> { Box::new(RandomPlay::new(StdRng::from_entropy())) };
+        let one = sharded(house(), 9, 10_000, 1_500, 1, &random).expect("all offered");
+        for threads in [2, 3, 8] {
+            assert_eq!(sharded(house(), 9, 10_000, 1_500, threads, &random), Ok(one));
+        }
+        assert_ne!(sharded(house(), 10, 10_000, 1_500, 2, &random), Ok(one));
+    }
+
+    #[test]
+    fn the_shards_add_up_to_the_runs_they_are_made_of() {
+        let dealer = || -> Box<dyn Policy> { Box::new(MimicDealer) };
+        let mut parts = Report::default();
+        for (shard, rounds) in [(0, 1_000), (1, 1_000), (2, 500)] {
+            parts.merge(&run(house(), shard_rng(4, shard), rounds, &mut MimicDealer).unwrap());
+        }
+        assert_eq!(sharded(house(), 4, 2_500, 1_000, 2, &dealer), Ok(parts));
+        assert_ne!(shard_rng(4, 0).gen::<u64>(), shard_rng(5, 0).gen::<u64>());
+    }
+
+    #[test]
+    fn a_refused_move_stops_the_run_with_the_table_s_reason() {
+        struct AlwaysSplit;
+        impl Policy for AlwaysSplit {
+            fn play(&mut self, _turn: &Turn, _rules: &Rules) -> Action {
+                Action::Split
+            }
+        }
+        let split = || -> Box<dyn Policy> { Box::new(AlwaysSplit) };
+        assert_eq!(run_sharded(house(), 1, 100, 2, split), Err(TableError::NotAPair));
+    }
+
+    #[test]
+    fn the_spread_is_a_sample_deviation_in_wagers() {
+        let report = Report { rounds: 2, wagered: 20, net: 0, squares: 200, hands: [0; 6] };
+        assert!((report.sd_per_round() - 2f64.sqrt()).abs() < 1e-12, "one win and one loss");
+        assert!((report.margin(1.0) - 1.0).abs() < 1e-12);
+        assert_eq!(Report { net: -1, ..report }.house_edge(), 0.05);
+    }
+}
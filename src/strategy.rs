@@ -0,0 +1,544 @@
+//! Basic strategy: the play with the best expected value for a hand, worked
+//! out from the table's own rules rather than copied from a printed chart.
+//!
+//! A chart is only right for the rules it was printed for, and a table here
+//! can deal one pack or eight, hit or stand on soft 17, peek or play European.
+//! So nothing is tabulated: every question is answered by computing the
+//! expected value of each legal move. The cards in view — the player's hand
+//! and the dealer's up card — come out of a full shoe, and every later card
+//! is drawn at the odds that leaves. That is the usual basic-strategy model:
+//! exact for an endless shoe, and for a real one blind to everything but the
+//! cards it is asked about, which is what separates it from counting.
+//!
+//! The same model answers for any shoe: [`expected_values_in`] takes the
+//! composition the cards came from and whether later cards leave it, which is
+//! how `blackjack-ev` values a hand in a shoe half dealt.
+
+use crate::analysis::{self, value_of, with_card, Deal, Shoe, VALUES};
+use crate::card::{Card, Rank, Suit};
+use crate::hand::Hand;
+use crate::rules::{HoleCard, Rules, Surrender};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Stand,
+    Hit,
+    Double,
+    Split,
+    Surrender,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let word = match self {
+            Self::Stand => "stand",
+            Self::Hit => "hit",
+            Self::Double => "double",
+            Self::Split => "split",
+            Self::Surrender => "surrender",
+        };
+        write!(f, "{}", word)
+    }
+}
+
+/// The moves besides hitting and standing that a table would accept on the
+/// hand right now; a live hand can always hit or stand.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Options {
+    pub double: bool,
+    /// Splits the seat may still make, this one included: 0 when the hand is
+    /// not a pair or the seat is at its limit.
+    pub splits: u8,
+    pub surrender: bool,
+}
+
+/// The expected value of every legal move, in units of the hand's wager, in
+/// the order stand, hit, double, split, surrender. This is basic strategy's
+/// question: the cards in view come out of a full shoe and every later card
+/// is drawn at the odds that leaves.
+pub fn expected_values(
+    hand: &Hand,
+    up: Card,
+    rules: &Rules,
+    options: Options,
+) -> Vec<(Action, f64)> {
+    expected_values_in(hand, up, &Shoe::new(rules.packs), Deal::Infinite, rules, options)
+}
+
+/// [`expected_values`] for a hand dealt from `shoe`, which still holds the
+/// player's cards and the up card: they are taken out here, so a shoe with
+/// every other card seen taken out asks about the shoe as it stands.
+///
+/// With [`Deal::Finite`] every card the player or the dealer draws leaves the
+/// shoe, and the values are exact but for two allowances the usual
+/// calculators make too. At a peek table the dealer is played as if the hole
+/// card were drawn after the player's cards, given that it makes no natural.
+/// And a split hand is played as if it were the only one drawn from the shoe
+/// the pair left. A finite deal works out the dealer once for every shoe a
+/// hand can stand on, so it is for analysis rather than every move at a table.
+pub fn expected_values_in(
+    hand: &Hand,
+    up: Card,
+    shoe: &Shoe,
+    deal: Deal,
+    rules: &Rules,
+    options: Options,
+) -> Vec<(Action, f64)> {
+    let mut model = Model::new(hand, up, shoe, deal, rules);
+    let shoe = model.shoe;
+    let mut values = vec![
+        (Action::Stand, model.stand(hand, &shoe, 1, 1)),
+        (Action::Hit, model.hit(hand, &shoe, 1)),
+    ];
+    if options.double {
+        let risk = if rules.original_bets_only { 1 } else { 2 };
+        values.push((Action::Double, model.double(hand, &shoe, risk)));
+    }
+    if options.splits > 0 && hand.is_pair() {
+        values.push((Action::Split, model.split(hand.cards()[0], options.splits)));
+    }
+    if options.surrender {
+        values.push((Action::Surrender, model.surrender()));
+    }
+    values
+}
+
+/// The move with the best expected value. Ties go to the later move in
+/// [`expected_values`]' order, though with floating-point values a true tie
+/// does not come up.
+pub fn best_action(hand: &Hand, up: Card, rules: &Rules, options: Options) -> Action {
+    expected_values(hand, up, rules, options)
+        .into_iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(Action::Stand, |(action, _)| action)
+}
+
+/// A running grade of one player's moves against basic strategy.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Accuracy {
+    pub decisions: u32,
+    /// Moves worth less than the best one on offer.
+    pub mistakes: u32,
+    /// Chips those mistakes gave up on average: the difference between the
+    /// best move and the one made, times the wager on the hand.
+    pub ev_lost: f64,
+}
+
+impl Accuracy {
+    /// Grades `played` on a hand with `wager` chips on it, given the
+    /// [`expected_values`] of every move that was open. A move that was not
+    /// open is not graded; the table refuses it anyway.
+    pub fn record(&mut self, values: &[(Action, f64)], played: Action, wager: u32) {
+        let Some(&(_, value)) = values.iter().find(|&&(action, _)| action == played) else {
+            return;
+        };
+        let best = values.iter().map(|&(_, value)| value).fold(f64::NEG_INFINITY, f64::max);
+        self.decisions += 1;
+        if value < best {
+            self.mistakes += 1;
+            self.ev_lost += (best - value) * f64::from(wager);
+        }
+    }
+
+    /// The share of moves that were mistakes, from 0 to 1.
+    pub fn error_rate(&self) -> f64 {
+        match self.decisions {
+            0 => 0.0,
+            decisions => f64::from(self.mistakes) / f64::from(decisions),
+        }
+    }
+}
+
+/// Everything one question needs: the shoe the cards in view leave, the
+/// dealer's chances against each shoe a hand can stand on, and the player's
+/// hitting values as they are worked out.
+struct Model<'a> {
+    rules: &'a Rules,
+    up: Card,
+    deal: Deal,
+    /// The shoe with the player's cards and the up card out of it.
+    shoe: Shoe,
+    /// The dealer's final total given no natural, 17 to 21 then bust, by the
+    /// shoe the dealer draws from. An infinite deal only ever has the one.
+    dealers: HashMap<Shoe, [f64; 6]>,
+    /// Hitting values by shoe, total, softness and what a natural would take.
+    hits: HashMap<(Shoe, u16, bool, u8), f64>,
+}
+
+impl<'a> Model<'a> {
+    fn new(hand: &Hand, up: Card, shoe: &Shoe, deal: Deal, rules: &'a Rules) -> Self {
+        let mut shoe = *shoe;
+        hand.cards().iter().chain([&up]).for_each(|card| shoe.remove(card.rank));
+        Self { rules, up, deal, shoe, dealers: HashMap::new(), hits: HashMap::new() }
+    }
+
+    /// The chance of a dealer natural still to be found out with `shoe` left
+    /// to deal the hole card from, which is only ever non-zero at a European
+    /// table: a peek has already ruled it out.
+    fn natural(&self, shoe: &Shoe) -> f64 {
+        if self.rules.hole_card == HoleCard::Peek {
+            return 0.0;
+        }
+        let odds = shoe.odds();
+        match self.up.rank.points() {
+            11 => odds[value_of(Rank::Ten)],
+            10 => odds[value_of(Rank::Ace)],
+            _ => 0.0,
+        }
+    }
+
+    /// The hole card cannot make a natural in the world where decisions
+    /// count, so the dealer is played from the odds after a peek.
+    fn dealer(&mut self, shoe: &Shoe) -> [f64; 6] {
+        if let Some(&dealer) = self.dealers.get(shoe) {
+            return dealer;
+        }
+        let hits_soft_17 = self.rules.dealer_hits_soft_17;
+        let peeked =
+            analysis::dealer_odds(self.up, shoe, hits_soft_17, self.deal).given_no_natural();
+        let mut dealer = [peeked.bust; 6];
+        dealer[..5].copy_from_slice(&peeked.totals);
+        self.dealers.insert(*shoe, dealer);
+        dealer
+    }
+
+    /// Standing with `stake` wagers on the hand and `shoe` left to the dealer,
+    /// a European natural taking `risk` of them: the whole stake, or under
+    /// original bets only the opening wager, or nothing from a split hand
+    /// when the seat answers for it once.
+    fn stand(&mut self, hand: &Hand, shoe: &Shoe, stake: u8, risk: u8) -> f64 {
+        let value = hand.total().value;
+        let won = if value > 21 {
+            -1.0
+        } else {
+            let dealer = self.dealer(shoe);
+            let against = (17..=21).zip(dealer).map(|(dealer, chance)| match value.cmp(&dealer) {
+                std::cmp::Ordering::Greater => chance,
+                std::cmp::Ordering::Equal => 0.0,
+                std::cmp::Ordering::Less => -chance,
+            });
+            against.sum::<f64>() + dealer[5]
+        };
+        let natural = self.natural(shoe);
+        (1.0 - natural) * f64::from(stake) * won - natural * f64::from(risk)
+    }
+
+    /// Hitting, then playing on as well as hitting and standing allow. Only
+    /// the total matters from here, so values are kept by total.
+    fn hit(&mut self, hand: &Hand, shoe: &Shoe, risk: u8) -> f64 {
+        let total = hand.total();
+        let key = (*shoe, total.value, total.soft, risk);
+        if let Some(&value) = self.hits.get(&key) {
+            return value;
+        }
+        let mut value = 0.0;
+        for (card, chance) in analysis::draws(shoe) {
+            let next = with_card(hand, card);
+            let left = shoe.after(card, self.deal);
+            let stand = self.stand(&next, &left, 1, risk);
+            let played = match next.total().value {
+                value if value >= 21 => stand,
+                _ => stand.max(self.hit(&next, &left, risk)),
+            };
+            value += chance * played;
+        }
+        self.hits.insert(key, value);
+        value
+    }
+
+    /// Twice the stake on exactly one more card.
+    fn double(&mut self, hand: &Hand, shoe: &Shoe, risk: u8) -> f64 {
+        let mut value = 0.0;
+        for (card, chance) in analysis::draws(shoe) {
+            value +=
+                chance * self.stand(&with_card(hand, card), &shoe.after(card, self.deal), 2, risk);
+        }
+        value
+    }
+
+    /// Early surrender gives half back even against a natural; otherwise a
+    /// European natural takes the lot.
+    fn surrender(&self) -> f64 {
+        if self.rules.surrender == Surrender::Early {
+            return -0.5;
+        }
+        let natural = self.natural(&self.shoe);
+        -0.5 * (1.0 - natural) - natural
+    }
+
+    /// Splitting and playing each hand its best way, resplitting whenever
+    /// another pair turns up and the seat has `splits` left — the usual
+    /// assumption, and right for every pair worth splitting in the first place.
+    fn split(&mut self, pair: Card, splits: u8) -> f64 {
+        let pair = value_of(pair.rank);
+        let aces = pair == 0;
+        let Rules {
+            split_aces_one_card,
+            split_ace_21_is_natural,
+            natural_pays,
+            original_bets_only,
+            ..
+        } = *self.rules;
+        let half = {
+            let mut half = Hand::from_cards(vec![Card { rank: VALUES[pair], suit: Suit::Spades }]);
+            half.push(half.cards()[0]);
+            half.split(aces && split_ace_21_is_natural)
+        };
+        let natural = f64::from(natural_pays.win) / f64::from(natural_pays.per);
+        // Under original bets only a European natural takes the opening wager
+        // once for the whole seat, below, and nothing from each hand.
+        let (risk, doubled) = if original_bets_only { (0, 0) } else { (1, 2) };
+        let shoe = self.shoe;
+        let play = |model: &mut Self, card: usize| {
+            let hand = with_card(&half, card);
+            let left = shoe.after(card, model.deal);
+            if hand.is_blackjack() {
+                return (1.0 - model.natural(&left)) * natural;
+            }
+            let stand = model.stand(&hand, &left, 1, risk);
+            if (aces && split_aces_one_card) || hand.total().value == 21 {
+                return stand;
+            }
+            let hit = model.hit(&hand, &left, risk);
+            stand.max(hit).max(model.double(&hand, &left, doubled))
+        };
+        let pair_odds = shoe.odds()[pair];
+        let others: f64 = analysis::draws(&shoe)
+            .filter(|&(card, _)| card != pair)
+            .map(|(card, chance)| chance * play(self, card))
+            .sum();
+        let other = if pair_odds < 1.0 { others / (1.0 - pair_odds) } else { 0.0 };
+        let kept = if pair_odds > 0.0 { play(self, pair) } else { 0.0 };
+        let seat = if original_bets_only { self.natural(&shoe) } else { 0.0 };
+        if aces && split_aces_one_card {
+            return 2.0 * (others + pair_odds * kept) - seat;
+        }
+        Hands { pair_odds, other, kept, memo: HashMap::new() }.value(2, splits - 1) - seat
+    }
+}
+
+/// Hands still waiting for their second card after a split, and what they
+/// are worth: each one either draws another of the pair and splits again,
+/// keeps the pair once splits run out, or draws anything else.
+struct Hands {
+    pair_odds: f64,
+    other: f64,
+    kept: f64,
+    memo: HashMap<(u8, u8), f64>,
+}
+
+impl Hands {
+    fn value(&mut self, open: u8, splits: u8) -> f64 {
+        if open == 0 {
+            return 0.0;
+        }
+        if let Some(&value) = self.memo.get(&(open, splits)) {
+            return value;
+        }
+        let paired = match splits {
+            0 => self.kept + self.value(open - 1, 0),
+            _ => self.value(open + 1, splits - 1),
+        };
+        let value = self.pair_odds * paired
+            + (1.0 - self.pair_odds) * (self.other + self.value(open - 1, splits));
+        self.memo.insert((open, splits), value);
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Rank::{self, *};
+    use crate::hand::fixtures::{card, hand};
+    use crate::rules::fixtures::house;
+
+    const ALL: Options = Options { double: true, splits: 3, surrender: false };
+
+    fn play(ranks: &[Rank], up: Rank, rules: &Rules) -> Action {
+        let options = Options { splits: if hand(ranks).is_pair() { 3 } else { 0 }, ..ALL };
+        best_action(&hand(ranks), card(up), rules, options)
+    }
+
+    /// Row by row against the multi-deck stands-on-soft-17 chart, dealer two
+    /// through ace, where the chart is not a coin toss.
+    fn chart(rules: &Rules, rows: &[(&[Rank], &str)]) {
+        let ups = [Two, Three, Four, Five, Six, Seven, Eight, Nine, Ten, Ace];
+        for (ranks, row) in rows {
+            for (&up, letter) in ups.iter().zip(row.chars()) {
+                let expected = match letter {
+                    'H' => Action::Hit,
+                    'S' => Action::Stand,
+                    'D' => Action::Double,
+                    'P' => Action::Split,
+                    'R' => Action::Surrender,
+                    _ => continue,
+                };
+                assert_eq!(play(ranks, up, rules), expected, "{:?} against {:?}", ranks, up);
+            }
+        }
+    }
+
+    #[test]
+    fn hard_totals_follow_the_six_deck_chart() {
+        chart(
+            &house(),
+            &[
+                (&[Two, Three], "HHHHHHHHHH"),
+                (&[Three, Five], "HHHHHHHHHH"),
+                (&[Four, Five], "HDDDDHHHHH"),
+                (&[Six, Four], "DDDDDDDDHH"),
+                (&[Six, Five], "DDDDDDDDD."),
+                (&[Ten, Two], "HHSSSHHHHH"),
+                (&[Ten, Three], "SSSSSHHHHH"),
+                (&[Ten, Four], "SSSSSHHHHH"),
+                (&[Ten, Five], "SSSSSHHHHH"),
+                (&[Ten, Six], "SSSSSHHHHH"),
+                (&[Ten, Seven], "SSSSSSSSSS"),
+            ],
+        );
+    }
+
+    #[test]
+    fn soft_totals_follow_the_six_deck_chart() {
+        chart(
+            &house(),
+            &[
+                (&[Ace, Two], "HHHDDHHHHH"),
+                (&[Ace, Four], "HHDDDHHHHH"),
+                (&[Ace, Six], "HDDDDHHHHH"),
+                (&[Ace, Seven], ".DDDDSSHHH"),
+                (&[Ace, Eight], "SSSS.SSSSS"),
+                (&[Ace, Nine], "SSSSSSSSSS"),
+            ],
+        );
+    }
+
+    #[test]
+    fn pairs_follow_the_six_deck_chart() {
+        chart(
+            &house(),
+            &[
+                (&[Three, Three], ".PPPPPHHHH"),
+                (&[Five, Five], "DDDDDDDDHH"),
+                (&[Six, Six], ".PPPPHHHHH"),
+                (&[Seven, Seven], "PPPPPPHHHH"),
+                (&[Eight, Eight], "PPPPPPPPPP"),
+                (&[Nine, Nine], "PPPPPSPPSS"),
+                (&[Ten, Ten], "SSSSSSSSSS"),
+                (&[Ace, Ace], "PPPPPPPPPP"),
+            ],
+        );
+    }
+
+    #[test]
+    fn surrender_is_only_advised_where_it_is_offered() {
+        let rules = Rules { surrender: Surrender::Late, ..house() };
+        let offered = Options { surrender: true, ..ALL };
+        assert_eq!(best_action(&hand(&[Ten, Six]), card(Ten), &rules, offered), Action::Surrender);
+        assert_eq!(best_action(&hand(&[Ten, Six]), card(Ten), &rules, ALL), Action::Hit);
+        assert_eq!(best_action(&hand(&[Ten, Seven]), card(Ten), &rules, offered), Action::Stand);
+    }
+
+    #[test]
+    fn the_dealers_soft_17_rule_changes_the_close_calls() {
+        let h17 = Rules { dealer_hits_soft_17: true, ..house() };
+        assert_eq!(play(&[Ace, Eight], Six, &house()), Action::Stand);
+        assert_eq!(play(&[Ace, Eight], Six, &h17), Action::Double);
+        assert_eq!(play(&[Six, Five], Ace, &h17), Action::Double);
+    }
+
+    #[test]
+    fn a_european_natural_makes_doubling_into_a_ten_less_attractive() {
+        let european = Rules { hole_card: HoleCard::European, ..house() };
+        assert_eq!(play(&[Six, Five], Ten, &house()), Action::Double);
+        assert_eq!(play(&[Six, Five], Ten, &european), Action::Hit);
+        assert_eq!(play(&[Eight, Eight], Ten, &european), Action::Hit);
+    }
+
+    #[test]
+    fn a_move_the_table_would_refuse_is_never_advised() {
+        let none = Options::default();
+        assert_eq!(best_action(&hand(&[Six, Five]), card(Six), &house(), none), Action::Hit);
+        assert_eq!(best_action(&hand(&[Eight, Eight]), card(Six), &house(), none), Action::Stand);
+    }
+
+    #[test]
+    fn only_a_move_worth_less_than_the_best_is_a_mistake_and_costs_its_wager() {
+        let values = expected_values(&hand(&[Six, Five]), card(Six), &house(), ALL);
+        let mut accuracy = Accuracy::default();
+        accuracy.record(&values, Action::Double, 10);
+        assert_eq!((accuracy.decisions, accuracy.mistakes, accuracy.ev_lost), (1, 0, 0.0));
+        accuracy.record(&values, Action::Hit, 10);
+        assert_eq!((accuracy.decisions, accuracy.mistakes), (2, 1));
+        let value = |played| values.iter().find(|&&(action, _)| action == played).expect("open").1;
+        assert_eq!(accuracy.ev_lost, (value(Action::Double) - value(Action::Hit)) * 10.0);
+        accuracy.record(&values, Action::Surrender, 10);
+        assert_eq!(accuracy.decisions, 2, "a move that was not open is not graded");
+        assert_eq!(accuracy.error_rate(), 0.5);
+    }
+
+    /// The published infinite-deck figures, which a shoe this deep is
+    /// indistinguishable from at three places.
+    #[test]
+    fn a_deep_shoe_reproduces_the_infinite_deck_values() {
+        let deep = Rules { packs: 200, ..house() };
+        let value = |ranks: &[Rank], up, action| {
+            let values = expected_values(&hand(ranks), card(up), &deep, ALL);
+            values.into_iter().find(|&(played, _)| played == action).expect("legal").1
+        };
+        let close = |value: f64, published: f64| (value - published).abs() < 0.001;
+        assert!(close(value(&[Ten, Six], Ten, Action::Stand), -0.540));
+        assert!(close(value(&[Ten, Six], Ten, Action::Hit), -0.540));
+        assert!(close(value(&[Ten, Ten], Ten, Action::Stand), 0.555));
+        assert!(close(value(&[Six, Five], Six, Action::Double), 0.668));
+    }
+
+    /// Two fives and a ten left behind the ten-six and the up ten: every line
+    /// of play can be followed by hand.
+    #[test]
+    fn a_finite_shoe_is_valued_exactly_with_the_cards_in_view_taken_out() {
+        let shoe = Shoe::from_counts([0, 0, 0, 0, 2, 1, 0, 0, 0, 3]);
+        let values = |deal| {
+            let options = Options { double: true, ..Options::default() };
+            let values =
+                expected_values_in(&hand(&[Ten, Six]), card(Ten), &shoe, deal, &house(), options);
+            values.into_iter().map(|(_, value)| value).collect::<Vec<f64>>()
+        };
+        let exact = values(Deal::Finite);
+        let expected = [-1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0];
+        exact
+            .iter()
+            .zip(expected)
+            .for_each(|(&value, expected)| assert!((value - expected).abs() < 1e-12));
+        assert_ne!(values(Deal::Infinite), exact);
+    }
+
+    #[test]
+    fn a_deep_shoe_dealt_card_by_card_is_worth_what_an_endless_one_is() {
+        let shoe = Shoe::new(8);
+        let rules = Rules { packs: 8, hole_card: HoleCard::European, ..house() };
+        let value = |ranks: &[Rank], up, deal| {
+            expected_values_in(&hand(ranks), card(up), &shoe, deal, &rules, ALL)
+        };
+        for (ranks, up) in [(&[Ten, Six], Ten), (&[Eight, Eight], Nine)] {
+            let (finite, infinite) =
+                (value(ranks, up, Deal::Finite), value(ranks, up, Deal::Infinite));
+            for ((action, finite), (_, infinite)) in finite.into_iter().zip(infinite) {
+                assert!((finite - infinite).abs() < 0.005, "{} on {:?}", action, ranks);
+            }
+        }
+    }
+
+    #[test]
+    fn values_come_in_a_fixed_order_and_within_the_stakes() {
+        let values = expected_values(&hand(&[Eight, Eight]), card(Ten), &house(), ALL);
+        let actions: Vec<Action> = values.iter().map(|&(action, _)| action).collect();
+        assert_eq!(actions, [Action::Stand, Action::Hit, Action::Double, Action::Split]);
+        for (action, value) in values {
+            assert!((-2.0..=2.0).contains(&value), "{} is worth {}", action, value);
+        }
+    }
+}
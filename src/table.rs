@@ -1,13 +1,19 @@
 //! The round state machine. Pure: commands in, events out, no I/O.
 
 use crate::card::{Card, Deck};
+use crate::count::{self, Count, Reading, Verdict};
 use crate::hand::{Hand, Total};
-use crate::rules::{self, Outcome, Rules};
+use crate::rules::{self, HoleCard, Odds, Outcome, Rules, Surrender};
+use crate::strategy::{self, Accuracy, Action, Options};
 use rand::Rng;
 use std::error::Error;
 use std::fmt;
+use std::time::{Duration, Instant};
 
 pub const MAX_SEATS: usize = 7;
+/// Spectators cost the table nothing but the lines it sends them, which is
+/// still a mailbox each, so they are capped too.
+pub const MAX_SPECTATORS: usize = 16;
 
 pub type SeatId = String;
 
@@ -15,6 +21,7 @@ pub type SeatId = String;
 pub enum Phase {
     WaitingForPlayers,
     Betting,
+    Insurance,
     PlayerTurns,
 }
 
@@ -23,23 +30,240 @@ pub enum Command {
     Bet(u32),
     Hit,
     Stand,
+    Double,
+    Split,
+    /// A side stake of up to half the wager against a dealer natural. A stake
+    /// of 0 declines insurance.
+    Insure(u32),
+    EvenMoney,
+    Surrender,
+    /// Says no to whatever is offered before the peek: insurance and even
+    /// money under an ace, early surrender under a ten.
+    Decline,
+    /// Keeps the seat and the chips but deals the seat no cards until it sits
+    /// in again, or bets.
+    SitOut,
+    SitIn,
+    /// Takes a free seat for a spectator, with the chips they brought.
+    Sit,
+}
+
+impl From<Action> for Command {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::Stand => Command::Stand,
+            Action::Hit => Command::Hit,
+            Action::Double => Command::Double,
+            Action::Split => Command::Split,
+            Action::Surrender => Command::Surrender,
+        }
+    }
+}
+
+/// A seat's decision as the table puts it: which of its hands is up, the
+/// cards in it, the dealer's up card, and the moves beyond hitting and
+/// standing that the table would take.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Turn {
+    pub hand: usize,
+    pub player: Hand,
+    pub up: Card,
+    pub options: Options,
 }
 
 /// What happened, not how to say it. Rendering lives in [`crate::protocol`].
-#[derive(Clone, Debug, PartialEq, Eq)]
+///
+/// `hand` counts a seat's hands from 0, left to right; it is only ever above 0
+/// after a split.
+#[derive(Clone, Debug, PartialEq)]
 pub enum Event {
-    Joined { name: String, bankroll: u32 },
-    Left { name: String },
-    BettingOpened { min_bet: u32 },
-    BetPlaced { name: String, amount: u32 },
-    Dealt { name: String, cards: Vec<Card>, total: Total },
-    DealerShows { card: Card },
-    TurnStarted { name: String },
-    Bust { name: String, total: Total },
-    Stood { name: String, total: Total },
-    DealerHand { cards: Vec<Card>, total: Total },
-    Settled { name: String, outcome: Outcome, payout: u32, bankroll: u32 },
-    OutOfChips { name: String },
+    /// `bot` marks one of the house's bots, which gives its seat up to any
+    /// player who needs it.
+    Joined {
+        name: String,
+        bankroll: u32,
+        bot: bool,
+    },
+    Left {
+        name: String,
+    },
+    Watching {
+        name: String,
+    },
+    StoppedWatching {
+        name: String,
+    },
+    /// The player's connection dropped. The seat is held for them, and plays
+    /// itself as cautiously as it can until they are back.
+    SteppedAway {
+        name: String,
+    },
+    Returned {
+        name: String,
+        bankroll: u32,
+    },
+    BettingOpened {
+        min_bet: u32,
+        max_bet: u32,
+        natural_pays: Odds,
+    },
+    BetPlaced {
+        name: String,
+        amount: u32,
+    },
+    Dealt {
+        name: String,
+        hand: usize,
+        cards: Vec<Card>,
+        total: Total,
+    },
+    /// `hole_card` is false at a European table, where the dealer's second
+    /// card is not dealt until the players are done.
+    DealerShows {
+        card: Card,
+        hole_card: bool,
+    },
+    InsuranceOffered,
+    /// Early surrender under a ten: the one decision taken before the peek.
+    SurrenderOffered,
+    /// The seat's answer to [`Event::SurrenderOffered`] was to play the hand.
+    SurrenderDeclined {
+        name: String,
+    },
+    EvenMoneyOffered {
+        name: String,
+    },
+    Insured {
+        name: String,
+        stake: u32,
+    },
+    EvenMoneyTaken {
+        name: String,
+    },
+    DealerChecked,
+    InsuranceSettled {
+        name: String,
+        stake: u32,
+        payout: u32,
+        bankroll: u32,
+    },
+    TurnStarted {
+        name: String,
+        hand: usize,
+    },
+    Doubled {
+        name: String,
+        hand: usize,
+        stake: u32,
+    },
+    Split {
+        name: String,
+        hand: usize,
+        stake: u32,
+    },
+    Surrendered {
+        name: String,
+    },
+    Bust {
+        name: String,
+        hand: usize,
+        total: Total,
+    },
+    Stood {
+        name: String,
+        hand: usize,
+        total: Total,
+    },
+    DealerHand {
+        cards: Vec<Card>,
+        total: Total,
+    },
+    Settled {
+        name: String,
+        hand: usize,
+        outcome: Outcome,
+        payout: u32,
+        bankroll: u32,
+    },
+    OutOfChips {
+        name: String,
+    },
+    /// The decision clock ran out on the player. The table answers for them
+    /// as cautiously as it can: a turn stands, an insurance offer is declined.
+    TurnTimedOut {
+        name: String,
+    },
+    /// The player sat out, by asking to or by letting the betting clock run
+    /// out before they wagered. Rounds are dealt without them until they sit
+    /// in or bet.
+    SatOut {
+        name: String,
+    },
+    SatIn {
+        name: String,
+    },
+    /// The seat sat out `rounds` deals in a row and is given up.
+    SeatReleased {
+        name: String,
+        rounds: u32,
+    },
+    /// How a player's moves measured up against basic strategy, told to the
+    /// table as they give up the seat. Only a player who made a move gets one.
+    Graded {
+        name: String,
+        accuracy: Accuracy,
+    },
+    /// A fresh shoe, so anyone keeping a count starts again.
+    Shuffled,
+    /// A counting trainer wants the count: the whole table's just before a
+    /// shuffle, or one player's when they ask. `true_count` is false for a
+    /// system played from the running count alone.
+    CountAsked {
+        true_count: bool,
+    },
+}
+
+impl Event {
+    /// The player or spectator the event is about. Events about the dealer or
+    /// the whole table have none.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::Joined { name, .. }
+            | Self::Left { name }
+            | Self::Watching { name }
+            | Self::StoppedWatching { name }
+            | Self::SteppedAway { name }
+            | Self::Returned { name, .. }
+            | Self::BetPlaced { name, .. }
+            | Self::Dealt { name, .. }
+            | Self::SurrenderDeclined { name }
+            | Self::EvenMoneyOffered { name }
+            | Self::Insured { name, .. }
+            | Self::EvenMoneyTaken { name }
+            | Self::InsuranceSettled { name, .. }
+            | Self::TurnStarted { name, .. }
+            | Self::Doubled { name, .. }
+            | Self::Split { name, .. }
+            | Self::Surrendered { name }
+            | Self::Bust { name, .. }
+            | Self::Stood { name, .. }
+            | Self::Settled { name, .. }
+            | Self::OutOfChips { name }
+            | Self::TurnTimedOut { name }
+            | Self::SatOut { name }
+            | Self::SatIn { name }
+            | Self::SeatReleased { name, .. }
+            | Self::Graded { name, .. } => Some(name),
+            Self::BettingOpened { .. }
+            | Self::DealerShows { .. }
+            | Self::InsuranceOffered
+            | Self::SurrenderOffered
+            | Self::DealerChecked
+            | Self::DealerHand { .. }
+            | Self::Shuffled
+            | Self::CountAsked { .. } => None,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -51,8 +275,26 @@ pub enum TableError {
     AlreadyBet,
     NotYourTurn,
     BetTooSmall { min_bet: u32 },
+    BetTooLarge { max_bet: u32 },
     InsufficientChips { bankroll: u32 },
+    NotOnTwoCards,
+    CannotAffordDouble { bankroll: u32 },
+    NotAPair,
+    SplitLimit { hands: usize },
+    CannotAffordSplit { bankroll: u32 },
+    InsuranceDecided,
+    InsuranceTooLarge { max: u32 },
+    NoNatural,
+    NoInsuranceOffered,
+    SurrenderNotOffered,
+    TooLateToSurrender,
+    NaturalNotSurrendered,
     ShoeExhausted,
+    AlreadySittingOut,
+    NotSittingOut,
+    GalleryFull,
+    NotATrainer,
+    NothingAsked,
 }
 
 impl fmt::Display for TableError {
@@ -65,48 +307,184 @@ impl fmt::Display for TableError {
             Self::AlreadyBet => write!(f, "your bet is already down for this round"),
             Self::NotYourTurn => write!(f, "wait your turn"),
             Self::BetTooSmall { min_bet } => write!(f, "the minimum bet is {}", min_bet),
+            Self::BetTooLarge { max_bet } => write!(f, "the maximum bet is {}", max_bet),
             Self::InsufficientChips { bankroll } => write!(f, "you only have {}", bankroll),
+            Self::NotOnTwoCards => write!(f, "you can only double on your first two cards"),
+            Self::CannotAffordDouble { bankroll } => {
+                write!(f, "doubling needs a second wager and you only have {}", bankroll)
+            }
+            Self::NotAPair => write!(f, "you can only split a pair"),
+            Self::SplitLimit { hands } => write!(f, "you can play at most {} hands", hands),
+            Self::CannotAffordSplit { bankroll } => {
+                write!(f, "splitting needs a second wager and you only have {}", bankroll)
+            }
+            Self::InsuranceDecided => write!(f, "you have already made your insurance decision"),
+            Self::InsuranceTooLarge { max } => write!(f, "you can insure for at most {}", max),
+            Self::NoNatural => write!(f, "even money is only offered on a blackjack"),
+            Self::NoInsuranceOffered => write!(f, "insurance is only offered against an ace"),
+            Self::SurrenderNotOffered => write!(f, "this table does not offer surrender"),
+            Self::TooLateToSurrender => {
+                write!(f, "you can only surrender as your first decision on a hand")
+            }
+            Self::NaturalNotSurrendered => write!(f, "a blackjack is paid, not surrendered"),
             Self::ShoeExhausted => write!(f, "the shoe ran out"),
+            Self::AlreadySittingOut => write!(f, "you are already sitting out"),
+            Self::NotSittingOut => write!(f, "you are not sitting out"),
+            Self::GalleryFull => {
+                write!(f, "there is no room left to watch ({} spectators)", MAX_SPECTATORS)
+            }
+            Self::NotATrainer => write!(f, "this table does not train counting"),
+            Self::NothingAsked => write!(f, "nobody has asked you for the count -- type quiz"),
         }
     }
 }
 
 impl Error for TableError {}
 
+/// A seat's opening wager is `bet`; once the cards are out, each of its
+/// `hands` carries a stake of its own, which is what lets a split or a double
+/// be settled hand by hand.
 #[derive(Clone, Debug)]
 struct Seat {
     id: SeatId,
     name: String,
     bankroll: u32,
     bet: u32,
+    hands: Vec<Spot>,
+    /// `None` until the seat answers an insurance offer, then the side stake
+    /// still on the felt: `Some(0)` once declined or settled.
+    insurance: Option<u32>,
+    /// Held for a player whose connection dropped. An away seat sits out
+    /// betting, declines insurance and stands whenever its turn comes.
+    away: bool,
+    /// Dealt no cards, at the player's request or because the betting clock
+    /// ran out on them. Unlike `away`, this lasts until the player says so.
+    sitting_out: bool,
+    /// Deals in a row this seat has sat out.
+    rounds_out: u32,
+    clock: Option<Clock>,
+    /// Every move made on a turn, graded against basic strategy.
+    accuracy: Accuracy,
+    /// The count the trainer last asked this seat for, until it answers.
+    quiz: Option<Reading>,
+    counting: count::Score,
+    /// One of the house's bots, played by [`crate::bot`] rather than a client.
+    bot: bool,
+}
+
+/// How long the table waits on a player before acting for them. A zero
+/// duration waits forever.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Clocks {
+    /// For an opening wager, from when betting opens or the player sits down.
+    pub betting: Duration,
+    /// For each move on a turn, and for an answer to an insurance offer.
+    pub decision: Duration,
+}
+
+/// What the table is waiting on one seat for, and since when. Each hit is a
+/// new decision with a clock of its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Clock {
+    round: u64,
+    wait: Wait,
+    since: Instant,
+}
+
+/// What the table is waiting on a seat for: an opening wager, an answer to
+/// an insurance or even-money offer, or a move on the hand with `cards` in it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wait {
+    Bet,
+    Insurance,
+    Decision { hand: usize, cards: usize },
+}
+
+/// Someone watching without a seat. They are known by name and bankroll so
+/// that they can take a seat as themselves when one frees up.
+#[derive(Clone, Debug)]
+struct Spectator {
+    id: SeatId,
+    name: String,
+    bankroll: u32,
+}
+
+#[derive(Clone, Debug)]
+struct Spot {
     hand: Hand,
+    bet: u32,
     done: bool,
+    even_money: bool,
+    surrendered: bool,
+}
+
+impl Spot {
+    fn new(bet: u32) -> Self {
+        Spot { hand: Hand::default(), bet, done: false, even_money: false, surrendered: false }
+    }
 }
 
 impl Seat {
-    fn new(id: &str, name: &str, bankroll: u32, done: bool) -> Self {
+    fn new(id: &str, name: &str, bankroll: u32) -> Self {
         Seat {
             id: id.to_string(),
             name: name.to_string(),
             bankroll,
             bet: 0,
-            hand: Hand::default(),
-            done,
+            hands: Vec::new(),
+            insurance: None,
+            away: false,
+            sitting_out: false,
+            rounds_out: 0,
+            clock: None,
+            accuracy: Accuracy::default(),
+            quiz: None,
+            counting: count::Score::default(),
+            bot: false,
         }
     }
 
-    fn dealt_event(&self) -> Event {
+    /// The hand being played: hands are played left to right, so it is the
+    /// first one still open. A seat that joined mid-round has none at all.
+    fn active(&self) -> Option<usize> {
+        self.hands.iter().position(|spot| !spot.done)
+    }
+
+    fn dealt_event(&self, hand: usize) -> Event {
+        let spot = &self.hands[hand];
         Event::Dealt {
             name: self.name.clone(),
-            cards: self.hand.cards().to_vec(),
-            total: self.hand.total(),
+            hand,
+            cards: spot.hand.cards().to_vec(),
+            total: spot.hand.total(),
+        }
+    }
+
+    fn closed_event(&self, hand: usize) -> Event {
+        let (name, total) = (self.name.clone(), self.hands[hand].hand.total());
+        if total.value > 21 {
+            Event::Bust { name, hand, total }
+        } else {
+            Event::Stood { name, hand, total }
         }
     }
 
+    /// The seat's grade, for when it is given up.
+    fn graded(&self) -> Option<Event> {
+        let accuracy = self.accuracy;
+        (accuracy.decisions > 0).then(|| Event::Graded { name: self.name.clone(), accuracy })
+    }
+
     fn clear(&mut self) {
-        self.hand.clear();
+        self.hands.clear();
         self.bet = 0;
-        self.done = false;
+        self.insurance = None;
+    }
+
+    /// Not waited on for anything: the table plays the seat's hands for it
+    /// and deals it no new ones.
+    fn absent(&self) -> bool {
+        self.away || self.sitting_out
     }
 }
 
@@ -115,10 +493,20 @@ impl Seat {
 pub struct Table<R: Rng> {
     rules: Rules,
     seats: Vec<Seat>,
+    spectators: Vec<Spectator>,
     dealer: Hand,
     deck: Deck,
     phase: Phase,
     turn: Option<SeatId>,
+    clocks: Clocks,
+    /// Counts deals, so that a clock left over from one round is never
+    /// mistaken for the same wait in the next.
+    round: u64,
+    /// Kept from the table's own events when the table trains counting.
+    trainer: Option<Count>,
+    /// Off for a table nobody reads the grades of, where valuing every
+    /// move would cost more than playing it.
+    grading: bool,
     rng: R,
 }
 
@@ -129,18 +517,47 @@ impl<R: Rng> Table<R> {
         Table {
             rules,
             seats: Vec::new(),
+            spectators: Vec::new(),
             dealer: Hand::default(),
             deck,
             phase: Phase::WaitingForPlayers,
             turn: None,
+            clocks: Clocks::default(),
+            round: 0,
+            trainer: None,
+            grading: true,
             rng,
         }
     }
 
+    /// A table built with [`Table::new`] waits on its players forever.
+    pub fn with_clocks(mut self, clocks: Clocks) -> Self {
+        self.clocks = clocks;
+        self
+    }
+
+    /// Makes the table a counting trainer for `system`: it asks every seat
+    /// for the count before each shuffle, and anyone for it on request.
+    pub fn with_trainer(mut self, system: count::System) -> Self {
+        self.trainer = Some(Count::new(system, self.rules.packs));
+        self
+    }
+
+    /// Stops grading moves, for the simulator: no seat at the table is
+    /// anyone's to tell.
+    pub fn ungraded(mut self) -> Self {
+        self.grading = false;
+        self
+    }
+
     pub fn phase(&self) -> Phase {
         self.phase
     }
 
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
     pub fn bankroll(&self, id: &str) -> Option<u32> {
         self.seats.iter().find(|seat| seat.id == id).map(|seat| seat.bankroll)
     }
@@ -151,30 +568,119 @@ impl<R: Rng> Table<R> {
         self.seats.iter().any(|seat| seat.id == id)
     }
 
-    /// Seats a player. Anyone arriving mid-round sits out until the next deal.
+    pub fn is_watching(&self, id: &str) -> bool {
+        self.spectators.iter().any(|spectator| spectator.id == id)
+    }
+
+    /// How many of the [`MAX_SEATS`] players hold, for the lobby's listing.
+    /// A bot's seat is anyone's for the asking, so it counts as free.
+    pub fn occupancy(&self) -> usize {
+        self.seats.iter().filter(|seat| !seat.bot).count()
+    }
+
+    /// The name a player or spectator goes by, for lines the table does not
+    /// produce itself, like chat.
+    pub fn name_of(&self, id: &str) -> Option<&str> {
+        let seat = self.seats.iter().find(|seat| seat.id == id).map(|seat| &seat.name);
+        let spectator = || self.spectators.iter().find(|spectator| spectator.id == id);
+        seat.or_else(|| spectator().map(|spectator| &spectator.name)).map(String::as_str)
+    }
+
+    /// How many players are in the game: seated, and neither sitting out nor
+    /// away. Bots are not players.
+    pub fn playing(&self) -> usize {
+        self.seats.iter().filter(|seat| !seat.bot && !seat.absent()).count()
+    }
+
+    /// How many of the [`MAX_SPECTATORS`] are watching.
+    pub fn audience(&self) -> usize {
+        self.spectators.len()
+    }
+
+    /// Lets someone watch without a seat. They see everything the players
+    /// see, which never includes the dealer's hole card.
+    pub fn watch(&mut self, id: &str, name: &str, bankroll: u32) -> Result<Vec<Event>, TableError> {
+        if self.is_seated(id) || self.is_watching(id) {
+            return Err(TableError::AlreadySeated);
+        }
+        if self.spectators.len() >= MAX_SPECTATORS {
+            return Err(TableError::GalleryFull);
+        }
+        let (id, name) = (id.to_string(), name.to_string());
+        self.spectators.push(Spectator { id, name: name.clone(), bankroll });
+        Ok(vec![Event::Watching { name }])
+    }
+
+    /// Seats a new player. Anyone arriving mid-round sits out until the next
+    /// deal.
     pub fn join(&mut self, id: &str, name: &str) -> Result<Vec<Event>, TableError> {
+        self.join_with_bankroll(id, name, self.rules.starting_bankroll)
+    }
+
+    /// Seats a returning player with the chips they brought. A bankroll that
+    /// cannot cover the minimum bet would only be shown the door at the end of
+    /// the first round, so it is refused at the door instead. At a full table
+    /// the last bot to sit down leaves to make room, forfeiting any wager.
+    pub fn join_with_bankroll(
+        &mut self,
+        id: &str,
+        name: &str,
+        bankroll: u32,
+    ) -> Result<Vec<Event>, TableError> {
+        if self.is_seated(id) {
+            return Err(TableError::AlreadySeated);
+        }
+        if bankroll < self.rules.min_bet {
+            return Err(TableError::InsufficientChips { bankroll });
+        }
+        let mut events = Vec::new();
+        if self.seats.len() >= MAX_SEATS {
+            let bot = self.seats.iter().rev().find(|seat| seat.bot).ok_or(TableError::TableFull)?;
+            let bot = bot.id.clone();
+            events.extend(self.leave(&bot)?);
+        }
+        self.seat(Seat::new(id, name, bankroll), &mut events);
+        Ok(events)
+    }
+
+    /// Seats one of the house's bots with the house's starting bankroll.
+    pub fn join_bot(&mut self, id: &str, name: &str) -> Result<Vec<Event>, TableError> {
         if self.is_seated(id) {
             return Err(TableError::AlreadySeated);
         }
         if self.seats.len() >= MAX_SEATS {
             return Err(TableError::TableFull);
         }
-        let sitting_out = self.phase == Phase::PlayerTurns;
-        let bankroll = self.rules.starting_bankroll;
-        self.seats.push(Seat::new(id, name, bankroll, sitting_out));
-        let mut events = vec![Event::Joined { name: name.to_string(), bankroll }];
-        self.open_betting_if_ready(&mut events);
+        let mut events = Vec::new();
+        let bot = Seat { bot: true, ..Seat::new(id, name, self.rules.starting_bankroll) };
+        self.seat(bot, &mut events);
         Ok(events)
     }
 
+    /// Betting can open here, and the trainer's count has to see it start a
+    /// round as surely as one opened by a settlement.
+    fn seat(&mut self, seat: Seat, events: &mut Vec<Event>) {
+        let (name, bankroll, bot) = (seat.name.clone(), seat.bankroll, seat.bot);
+        let from = events.len();
+        self.seats.push(seat);
+        events.push(Event::Joined { name, bankroll, bot });
+        self.open_betting_if_ready(events);
+        self.observe(&events[from..]);
+    }
+
     /// Removes a player. A wager already on the felt is forfeited, and the
     /// round moves on rather than deadlocking whether the table was waiting on
     /// them to act or waiting on them to bet.
     pub fn leave(&mut self, id: &str) -> Result<Vec<Event>, TableError> {
+        if let Some(index) = self.spectators.iter().position(|spectator| spectator.id == id) {
+            return Ok(vec![Event::StoppedWatching { name: self.spectators.remove(index).name }]);
+        }
         let Some(index) = self.seats.iter().position(|seat| seat.id == id) else {
             return Ok(Vec::new());
         };
-        let mut events = vec![Event::Left { name: self.seats.remove(index).name }];
+        let seat = self.seats.remove(index);
+        let mut events: Vec<Event> = seat.graded().into_iter().collect();
+        events.push(Event::Left { name: seat.name });
         if self.seats.is_empty() {
             self.abandon_round();
             return Ok(events);
@@ -183,74 +689,591 @@ impl<R: Rng> Table<R> {
             self.advance_turn(&mut events)?;
         }
         self.deal_if_all_wagered(&mut events)?;
+        self.peek_if_all_insured(&mut events)?;
+        self.observe(&events);
+        Ok(events)
+    }
+
+    /// Holds the seat of a player whose connection dropped, so that they can
+    /// come back to it with [`Table::resume`]. Nobody waits on them meanwhile:
+    /// a turn that is theirs, now or later, is stood on their behalf. A
+    /// spectator has nothing to hold, and simply leaves.
+    pub fn step_away(&mut self, id: &str) -> Result<Vec<Event>, TableError> {
+        if self.is_watching(id) {
+            return self.leave(id);
+        }
+        let Ok(seat) = self.seat_mut(id) else {
+            return Ok(Vec::new());
+        };
+        seat.away = true;
+        let mut events = vec![Event::SteppedAway { name: seat.name.clone() }];
+        self.stop_waiting_on(id, &mut events)?;
+        self.observe(&events);
+        Ok(events)
+    }
+
+    /// Hands a held seat — chips, wager and cards — to the player's new
+    /// connection, and the turn with it if the turn is the seat's. The old
+    /// connection is often not yet known to be dead, so the seat may not have
+    /// been stood for it.
+    pub fn resume(&mut self, old: &str, new: &str) -> Result<Vec<Event>, TableError> {
+        if self.is_seated(new) {
+            return Err(TableError::AlreadySeated);
+        }
+        let seat = self.seat_mut(old)?;
+        seat.id = new.to_string();
+        seat.away = false;
+        let returned = Event::Returned { name: seat.name.clone(), bankroll: seat.bankroll };
+        if self.turn.as_deref() == Some(old) {
+            self.turn = Some(new.to_string());
+        }
+        Ok(vec![returned])
+    }
+
+    /// What a returning player or a new spectator needs to pick the round up,
+    /// as the events they would have seen: the dealer's up card, every hand on
+    /// the felt, and whatever the table is waiting on.
+    pub fn summary(&self, id: &str) -> Vec<Event> {
+        let seat = self.seats.iter().find(|seat| seat.id == id);
+        if seat.is_none() && !self.is_watching(id) {
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        if self.phase == Phase::Betting && seat.is_some_and(|seat| seat.bet == 0) {
+            let Rules { min_bet, max_bet, natural_pays, .. } = self.rules;
+            events.push(Event::BettingOpened { min_bet, max_bet, natural_pays });
+        }
+        if let Some(&card) = self.dealer.cards().first() {
+            events.push(Event::DealerShows { card, hole_card: self.dealer.cards().len() > 1 });
+        }
+        for seat in &self.seats {
+            events.extend((0..seat.hands.len()).map(|hand| seat.dealt_event(hand)));
+        }
+        let undecided = seat.is_some_and(|seat| !seat.hands.is_empty() && seat.insurance.is_none());
+        if self.phase == Phase::Insurance && undecided {
+            events.push(self.pre_peek_offer());
+        }
+        let turn = self.seats.iter().find(|seat| self.turn.as_ref() == Some(&seat.id));
+        if let Some((seat, hand)) = turn.and_then(|seat| Some((seat, seat.active()?))) {
+            events.push(Event::TurnStarted { name: seat.name.clone(), hand });
+        }
+        events
+    }
+
+    /// What a seat on its turn has to decide with. Only a seat whose turn it
+    /// is has one.
+    pub fn turn(&self, id: &str) -> Result<Turn, TableError> {
+        let hand = self.require_turn(id)?;
+        let seat = self.seats.iter().find(|seat| seat.id == id).ok_or(TableError::UnknownSeat)?;
+        let up = *self.dealer.cards().first().ok_or(TableError::WrongPhase)?;
+        let player = seat.hands[hand].hand.clone();
+        Ok(Turn { hand, player, up, options: self.options(seat, hand) })
+    }
+
+    /// Basic strategy for the hand a seat is to play, and which hand that is.
+    /// Only asked on the seat's own turn, so it never advises a move the
+    /// table would refuse.
+    pub fn hint(&self, id: &str) -> Result<(usize, Action), TableError> {
+        let turn = self.turn(id)?;
+        Ok((turn.hand, strategy::best_action(&turn.player, turn.up, &self.rules, turn.options)))
+    }
+
+    /// The moves `double`, `split` and `surrender` would accept on one of a
+    /// seat's hands, checked the same way they check.
+    fn options(&self, seat: &Seat, hand: usize) -> Options {
+        let spot = &seat.hands[hand];
+        let two_cards = spot.hand.cards().len() == 2;
+        let affords = seat.bankroll >= spot.bet;
+        let most_hands = self.rules.max_resplits as usize + 2;
+        let splits = match spot.hand.is_pair() && affords {
+            true => most_hands.saturating_sub(seat.hands.len()) as u8,
+            false => 0,
+        };
+        let surrender =
+            self.rules.surrender != Surrender::None && seat.hands.len() == 1 && two_cards;
+        Options { double: two_cards && affords, splits, surrender }
+    }
+
+    /// Asks one seat for the count as it stands, and returns the question to
+    /// put to them.
+    pub fn quiz(&mut self, id: &str) -> Result<Event, TableError> {
+        let reading = self.trainer.as_ref().ok_or(TableError::NotATrainer)?.reading();
+        self.seat_mut(id)?.quiz = Some(reading);
+        Ok(Event::CountAsked { true_count: reading.true_count.is_some() })
+    }
+
+    /// Marks a seat's answer to the last count it was asked for.
+    pub fn answer_count(
+        &mut self,
+        id: &str,
+        running: i32,
+        true_count: Option<i32>,
+    ) -> Result<Verdict, TableError> {
+        if self.trainer.is_none() {
+            return Err(TableError::NotATrainer);
+        }
+        let seat = self.seat_mut(id)?;
+        let reading = seat.quiz.take().ok_or(TableError::NothingAsked)?;
+        Ok(seat.counting.mark(reading, running, true_count))
+    }
+
+    /// How a seated player's moves so far measure up against basic strategy.
+    pub fn accuracy(&self, id: &str) -> Result<Accuracy, TableError> {
+        let seat = self.seats.iter().find(|seat| seat.id == id);
+        seat.map(|seat| seat.accuracy).ok_or(TableError::UnknownSeat)
+    }
+
+    /// Moves the table's clocks on to `now`, answering for every player whose
+    /// time is up. The table has no clock of its own: whoever owns it calls
+    /// this every so often, and a clock starts at the first tick that sees
+    /// the table waiting, so a wait is never cut short by more than one tick.
+    pub fn tick(&mut self, now: Instant) -> Result<Vec<Event>, TableError> {
+        let waits: Vec<Option<Wait>> =
+            self.seats.iter().map(|seat| self.waiting_on(seat)).collect();
+        let (round, clocks) = (self.round, self.clocks);
+        let mut expired = Vec::new();
+        for (seat, wait) in self.seats.iter_mut().zip(waits) {
+            let Some(wait) = wait else {
+                seat.clock = None;
+                continue;
+            };
+            let limit = match wait {
+                Wait::Bet => clocks.betting,
+                Wait::Insurance | Wait::Decision { .. } => clocks.decision,
+            };
+            match seat.clock {
+                Some(clock) if clock.round == round && clock.wait == wait => {
+                    if limit > Duration::ZERO && now.duration_since(clock.since) >= limit {
+                        expired.push((seat.id.clone(), wait));
+                    }
+                }
+                _ => seat.clock = Some(Clock { round, wait, since: now }),
+            }
+        }
+        let mut events = Vec::new();
+        for (id, wait) in expired {
+            self.time_out(&id, wait, &mut events)?;
+        }
+        self.deal_if_all_wagered(&mut events)?;
+        self.peek_if_all_insured(&mut events)?;
+        self.observe(&events);
         Ok(events)
     }
 
     pub fn apply(&mut self, id: &str, command: Command) -> Result<Vec<Event>, TableError> {
-        match command {
+        // Graded before the move is made, because the move can end the round
+        // and a seat that runs out of chips with it; a refused move is not
+        // graded at all.
+        let ungraded = self.grade(id, command);
+        let result = match command {
             Command::Bet(amount) => self.bet(id, amount),
             Command::Hit => self.hit(id),
             Command::Stand => self.stand(id),
+            Command::Double => self.double(id),
+            Command::Split => self.split(id),
+            Command::Insure(stake) => self.insure(id, stake),
+            Command::EvenMoney => self.even_money(id),
+            Command::Surrender => self.surrender(id),
+            Command::Decline => self.decline(id),
+            Command::SitOut => self.sit_out(id),
+            Command::SitIn => self.sit_in(id),
+            Command::Sit => self.sit(id),
+        };
+        match (&result, ungraded) {
+            (Ok(events), _) => self.observe(events),
+            (Err(_), Some(ungraded)) => {
+                if let Ok(seat) = self.seat_mut(id) {
+                    seat.accuracy = ungraded;
+                }
+            }
+            (Err(_), None) => {}
+        }
+        result
+    }
+
+    /// Feeds the trainer's count every event the table hands out, in order.
+    fn observe(&mut self, events: &[Event]) {
+        if let Some(count) = &mut self.trainer {
+            events.iter().for_each(|event| count.observe(event));
+        }
+    }
+
+    /// Records `command` in the seat's grade if it is a move on the seat's
+    /// turn, and returns the grade as it was.
+    fn grade(&mut self, id: &str, command: Command) -> Option<Accuracy> {
+        let played = match command {
+            Command::Hit => Action::Hit,
+            Command::Stand => Action::Stand,
+            Command::Double => Action::Double,
+            Command::Split => Action::Split,
+            Command::Surrender => Action::Surrender,
+            _ => return None,
+        };
+        if !self.grading {
+            return None;
+        }
+        let hand = self.require_turn(id).ok()?;
+        let up = *self.dealer.cards().first()?;
+        let seat = self.seats.iter().find(|seat| seat.id == id && !seat.bot)?;
+        let spot = &seat.hands[hand];
+        let options = self.options(seat, hand);
+        let values = strategy::expected_values(&spot.hand, up, &self.rules, options);
+        let wager = spot.bet;
+        let seat = self.seat_mut(id).ok()?;
+        let ungraded = seat.accuracy;
+        seat.accuracy.record(&values, played, wager);
+        Some(ungraded)
+    }
+
+    /// What the table is waiting on a seat to do, if anything: whatever is
+    /// playing it can tell when to act without waiting to be told.
+    pub fn waiting_for(&self, id: &str) -> Option<Wait> {
+        self.seats.iter().find(|seat| seat.id == id).and_then(|seat| self.waiting_on(seat))
+    }
+
+    /// What the table is waiting on this seat to do, if anything.
+    fn waiting_on(&self, seat: &Seat) -> Option<Wait> {
+        match self.phase {
+            Phase::Betting if seat.bet == 0 && !seat.absent() => Some(Wait::Bet),
+            Phase::Insurance if !seat.hands.is_empty() && seat.insurance.is_none() => {
+                Some(Wait::Insurance)
+            }
+            Phase::PlayerTurns if self.turn.as_ref() == Some(&seat.id) => {
+                let hand = seat.active()?;
+                Some(Wait::Decision { hand, cards: seat.hands[hand].hand.cards().len() })
+            }
+            _ => None,
+        }
+    }
+
+    /// Answers for a seat whose clock ran out, unless an earlier answer in
+    /// the same tick already moved the table on.
+    fn time_out(
+        &mut self,
+        id: &str,
+        wait: Wait,
+        events: &mut Vec<Event>,
+    ) -> Result<(), TableError> {
+        let Some(seat) = self.seats.iter().find(|seat| seat.id == id) else {
+            return Ok(());
+        };
+        if self.waiting_on(seat) != Some(wait) {
+            return Ok(());
+        }
+        let declined = self.declined(&seat.name);
+        let seat = self.seat_mut(id)?;
+        let name = seat.name.clone();
+        match wait {
+            Wait::Bet => {
+                seat.sitting_out = true;
+                events.push(Event::SatOut { name });
+            }
+            Wait::Insurance => {
+                seat.insurance = Some(0);
+                events.push(Event::TurnTimedOut { name });
+                events.push(declined);
+            }
+            Wait::Decision { .. } => {
+                events.push(Event::TurnTimedOut { name });
+                events.extend(self.stand(id)?);
+            }
         }
+        Ok(())
     }
 
     fn bet(&mut self, id: &str, amount: u32) -> Result<Vec<Event>, TableError> {
         if self.phase != Phase::Betting {
             return Err(TableError::WrongPhase);
         }
-        let min_bet = self.rules.min_bet;
+        let rules = self.rules;
         let seat = self.seat_mut(id)?;
-        check_wager(seat, amount, min_bet)?;
+        check_wager(seat, amount, &rules)?;
+        let mut events = Vec::new();
+        if seat.sitting_out {
+            seat.sitting_out = false;
+            seat.rounds_out = 0;
+            events.push(Event::SatIn { name: seat.name.clone() });
+        }
         seat.bankroll -= amount;
         seat.bet = amount;
-        let mut events = vec![Event::BetPlaced { name: seat.name.clone(), amount }];
+        events.push(Event::BetPlaced { name: seat.name.clone(), amount });
         self.deal_if_all_wagered(&mut events)?;
         Ok(events)
     }
 
+    /// A wager already down this round has to be played: sitting out takes
+    /// effect from the next deal. A hand already dealt is stood for the seat,
+    /// as an away seat's would be.
+    fn sit_out(&mut self, id: &str) -> Result<Vec<Event>, TableError> {
+        let phase = self.phase;
+        let seat = self.seat_mut(id)?;
+        if seat.sitting_out {
+            return Err(TableError::AlreadySittingOut);
+        }
+        if phase == Phase::Betting && seat.bet > 0 {
+            return Err(TableError::AlreadyBet);
+        }
+        seat.sitting_out = true;
+        let mut events = vec![Event::SatOut { name: seat.name.clone() }];
+        self.stop_waiting_on(id, &mut events)?;
+        Ok(events)
+    }
+
+    /// A spectator who cannot be seated — the table is full, or their chips
+    /// are short of the minimum — goes on watching.
+    fn sit(&mut self, id: &str) -> Result<Vec<Event>, TableError> {
+        let Some(index) = self.spectators.iter().position(|spectator| spectator.id == id) else {
+            let seated = self.is_seated(id);
+            return Err(if seated { TableError::AlreadySeated } else { TableError::UnknownSeat });
+        };
+        let Spectator { name, bankroll, .. } = self.spectators[index].clone();
+        let events = self.join_with_bankroll(id, &name, bankroll)?;
+        self.spectators.remove(index);
+        Ok(events)
+    }
+
+    fn sit_in(&mut self, id: &str) -> Result<Vec<Event>, TableError> {
+        let seat = self.seat_mut(id)?;
+        if !seat.sitting_out {
+            return Err(TableError::NotSittingOut);
+        }
+        seat.sitting_out = false;
+        seat.rounds_out = 0;
+        Ok(vec![Event::SatIn { name: seat.name.clone() }])
+    }
+
+    /// Lets the round go on without a seat that has just become absent:
+    /// declines an insurance offer it has not answered, stands the hand it is
+    /// on if the turn is its own, and re-checks both gates it may be holding.
+    fn stop_waiting_on(&mut self, id: &str, events: &mut Vec<Event>) -> Result<(), TableError> {
+        let phase = self.phase;
+        let declined = self.name_of(id).map(|name| self.declined(name));
+        let seat = self.seat_mut(id)?;
+        if phase == Phase::Insurance && !seat.hands.is_empty() && seat.insurance.is_none() {
+            seat.insurance = Some(0);
+            events.extend(declined);
+        }
+        if self.turn.as_deref() == Some(id) {
+            self.advance_turn(events)?;
+        }
+        self.deal_if_all_wagered(events)?;
+        self.peek_if_all_insured(events)
+    }
+
     /// The only gate that starts a round. It has to be re-checked whenever the
     /// set of seats changes, not just when a bet lands, or a player leaving
-    /// during betting wedges the table on a wager that will never arrive.
+    /// during betting wedges the table on a wager that will never arrive. An
+    /// away or sat-out seat is not waited on; it sits the round out.
     fn deal_if_all_wagered(&mut self, events: &mut Vec<Event>) -> Result<(), TableError> {
-        if self.phase != Phase::Betting || self.seats.iter().any(|seat| seat.bet == 0) {
+        let waiting = self.seats.iter().any(|seat| seat.bet == 0 && !seat.absent());
+        let wagered = self.seats.iter().any(|seat| seat.bet > 0);
+        if self.phase != Phase::Betting || waiting || !wagered {
             return Ok(());
         }
         self.deal_round(events)
     }
 
     fn hit(&mut self, id: &str) -> Result<Vec<Event>, TableError> {
-        self.require_turn(id)?;
+        let hand = self.require_turn(id)?;
         let card = self.draw()?;
         let seat = self.seat_mut(id)?;
-        seat.hand.push(card);
-        let (name, total) = (seat.name.clone(), seat.hand.total());
-        let mut events = vec![seat.dealt_event()];
-        if total.value < 21 {
+        seat.hands[hand].hand.push(card);
+        let mut events = vec![seat.dealt_event(hand)];
+        if seat.hands[hand].hand.total().value < 21 {
             return Ok(events);
         }
-        seat.done = true;
-        events.push(hand_closed(name, total));
+        seat.hands[hand].done = true;
+        events.push(seat.closed_event(hand));
         self.advance_turn(&mut events)?;
         Ok(events)
     }
 
     fn stand(&mut self, id: &str) -> Result<Vec<Event>, TableError> {
-        self.require_turn(id)?;
+        let hand = self.require_turn(id)?;
+        let seat = self.seat_mut(id)?;
+        seat.hands[hand].done = true;
+        let mut events = vec![seat.closed_event(hand)];
+        self.advance_turn(&mut events)?;
+        Ok(events)
+    }
+
+    /// A second wager equal to the first buys exactly one more card, and the
+    /// hand is closed whatever that card is.
+    fn double(&mut self, id: &str) -> Result<Vec<Event>, TableError> {
+        let hand = self.require_turn(id)?;
+        let seat = self.seat_mut(id)?;
+        let spot = &seat.hands[hand];
+        if spot.hand.cards().len() != 2 {
+            return Err(TableError::NotOnTwoCards);
+        }
+        if seat.bankroll < spot.bet {
+            return Err(TableError::CannotAffordDouble { bankroll: seat.bankroll });
+        }
+        let card = self.draw()?;
         let seat = self.seat_mut(id)?;
-        seat.done = true;
-        let mut events = vec![Event::Stood { name: seat.name.clone(), total: seat.hand.total() }];
+        let spot = &mut seat.hands[hand];
+        seat.bankroll -= spot.bet;
+        spot.bet *= 2;
+        spot.hand.push(card);
+        spot.done = true;
+        let stake = spot.bet;
+        let mut events = vec![
+            Event::Doubled { name: seat.name.clone(), hand, stake },
+            seat.dealt_event(hand),
+            seat.closed_event(hand),
+        ];
         self.advance_turn(&mut events)?;
         Ok(events)
     }
 
-    fn require_turn(&self, id: &str) -> Result<(), TableError> {
+    /// Splits a pair into two hands, each with a wager equal to the first and
+    /// a fresh second card. The new hand is played straight after this one,
+    /// so a seat's hands stay in left-to-right order however often it splits.
+    fn split(&mut self, id: &str) -> Result<Vec<Event>, TableError> {
+        let hand = self.require_turn(id)?;
+        let Rules { max_resplits, split_aces_one_card, split_ace_21_is_natural, .. } = self.rules;
+        let most_hands = max_resplits as usize + 2;
+        let seat = self.seat_mut(id)?;
+        let spot = &seat.hands[hand];
+        if !spot.hand.is_pair() {
+            return Err(TableError::NotAPair);
+        }
+        if seat.hands.len() >= most_hands {
+            return Err(TableError::SplitLimit { hands: most_hands });
+        }
+        if seat.bankroll < spot.bet {
+            return Err(TableError::CannotAffordSplit { bankroll: seat.bankroll });
+        }
+        let cards = [self.draw()?, self.draw()?];
+        let seat = self.seat_mut(id)?;
+        let aces = seat.hands[hand].hand.holds_ace();
+        let stake = seat.hands[hand].bet;
+        seat.bankroll -= stake;
+        let second = seat.hands[hand].hand.split(aces && split_ace_21_is_natural);
+        seat.hands.insert(hand + 1, Spot { hand: second, ..Spot::new(stake) });
+        let mut events = vec![Event::Split { name: seat.name.clone(), hand, stake }];
+        for (offset, card) in cards.into_iter().enumerate() {
+            let spot = &mut seat.hands[hand + offset];
+            spot.hand.push(card);
+            spot.done = (aces && split_aces_one_card) || spot.hand.total().value == 21;
+            events.push(seat.dealt_event(hand + offset));
+        }
+        for closed in (hand..hand + 2).filter(|&index| seat.hands[index].done) {
+            events.push(seat.closed_event(closed));
+        }
+        if seat.hands[hand].done {
+            self.advance_turn(&mut events)?;
+        }
+        Ok(events)
+    }
+
+    fn insure(&mut self, id: &str, stake: u32) -> Result<Vec<Event>, TableError> {
+        let ace = self.up_card_points() == 11;
+        let seat = self.undecided_insurance(id)?;
+        if !ace {
+            return Err(TableError::NoInsuranceOffered);
+        }
+        let max = seat.bet / 2;
+        if stake > max {
+            return Err(TableError::InsuranceTooLarge { max });
+        }
+        if stake > seat.bankroll {
+            return Err(TableError::InsufficientChips { bankroll: seat.bankroll });
+        }
+        seat.bankroll -= stake;
+        seat.insurance = Some(stake);
+        let mut events = vec![Event::Insured { name: seat.name.clone(), stake }];
+        self.peek_if_all_insured(&mut events)?;
+        Ok(events)
+    }
+
+    /// A natural facing an ace can lock in a 1:1 win before the dealer peeks.
+    /// It is the same bet as insuring a blackjack for half the wager, whose
+    /// result is +1 whatever the hole card is, without the side stake.
+    fn even_money(&mut self, id: &str) -> Result<Vec<Event>, TableError> {
+        let ace = self.up_card_points() == 11;
+        let seat = self.undecided_insurance(id)?;
+        if !ace {
+            return Err(TableError::NoInsuranceOffered);
+        }
+        if !seat.hands[0].hand.is_blackjack() {
+            return Err(TableError::NoNatural);
+        }
+        seat.hands[0].even_money = true;
+        seat.insurance = Some(0);
+        let mut events = vec![Event::EvenMoneyTaken { name: seat.name.clone() }];
+        self.peek_if_all_insured(&mut events)?;
+        Ok(events)
+    }
+
+    /// Surrender is the first decision on an unsplit hand or not at all. Early
+    /// surrender can also be the seat's answer to the offer made before the
+    /// peek, insurance under an ace or surrender itself under a ten, which is
+    /// what lets it beat the dealer's peek.
+    fn surrender(&mut self, id: &str) -> Result<Vec<Event>, TableError> {
+        let rule = self.rules.surrender;
+        if rule == Surrender::None {
+            return Err(TableError::SurrenderNotOffered);
+        }
+        let seat = match self.phase {
+            Phase::Insurance if rule == Surrender::Early => {
+                let seat = self.undecided_insurance(id)?;
+                seat.insurance = Some(0);
+                seat
+            }
+            _ => {
+                self.require_turn(id)?;
+                self.seat_mut(id)?
+            }
+        };
+        if seat.hands.len() != 1 || seat.hands[0].hand.cards().len() != 2 {
+            return Err(TableError::TooLateToSurrender);
+        }
+        if seat.hands[0].hand.is_blackjack() {
+            return Err(TableError::NaturalNotSurrendered);
+        }
+        let spot = &mut seat.hands[0];
+        spot.surrendered = true;
+        spot.done = true;
+        let mut events = vec![Event::Surrendered { name: seat.name.clone() }];
+        match self.phase {
+            Phase::Insurance => self.peek_if_all_insured(&mut events)?,
+            _ => self.advance_turn(&mut events)?,
+        }
+        Ok(events)
+    }
+
+    /// The answer that takes nothing offered before the peek.
+    fn decline(&mut self, id: &str) -> Result<Vec<Event>, TableError> {
+        let seat = self.undecided_insurance(id)?;
+        seat.insurance = Some(0);
+        let name = seat.name.clone();
+        let mut events = vec![self.declined(&name)];
+        self.peek_if_all_insured(&mut events)?;
+        Ok(events)
+    }
+
+    fn undecided_insurance(&mut self, id: &str) -> Result<&mut Seat, TableError> {
+        if self.phase != Phase::Insurance {
+            return Err(TableError::WrongPhase);
+        }
+        let seat = self.seat_mut(id)?;
+        if seat.hands.is_empty() || seat.insurance.is_some() {
+            return Err(TableError::InsuranceDecided);
+        }
+        Ok(seat)
+    }
+
+    /// Returns which of the seat's hands is to act.
+    fn require_turn(&self, id: &str) -> Result<usize, TableError> {
         if self.phase != Phase::PlayerTurns {
             return Err(TableError::WrongPhase);
         }
         if self.turn.as_deref() != Some(id) {
             return Err(TableError::NotYourTurn);
         }
-        Ok(())
+        let seat = self.seats.iter().find(|seat| seat.id == id);
+        seat.and_then(Seat::active).ok_or(TableError::UnknownSeat)
     }
 
     fn seat_mut(&mut self, id: &str) -> Result<&mut Seat, TableError> {
@@ -262,49 +1285,174 @@ impl<R: Rng> Table<R> {
             return;
         }
         self.phase = Phase::Betting;
-        events.push(Event::BettingOpened { min_bet: self.rules.min_bet });
+        let Rules { min_bet, max_bet, natural_pays, .. } = self.rules;
+        events.push(Event::BettingOpened { min_bet, max_bet, natural_pays });
     }
 
     fn deal_round(&mut self, events: &mut Vec<Event>) -> Result<(), TableError> {
-        self.replenish_at_cut_card();
+        self.round += 1;
+        if self.replenish_at_cut_card() {
+            self.announce_shuffle(events);
+        }
         self.deal_opening_cards()?;
+        for seat in self.seats.iter_mut() {
+            seat.rounds_out = if seat.sitting_out { seat.rounds_out + 1 } else { 0 };
+        }
         self.phase = Phase::PlayerTurns;
-        events.extend(self.seats.iter().map(Seat::dealt_event));
+        let dealt = self.seats.iter().filter(|seat| !seat.hands.is_empty());
+        events.extend(dealt.map(|seat| seat.dealt_event(0)));
         if let Some(&card) = self.dealer.cards().first() {
-            events.push(Event::DealerShows { card });
+            let hole_card = self.rules.hole_card == HoleCard::Peek;
+            events.push(Event::DealerShows { card, hole_card });
         }
-        for seat in self.seats.iter_mut() {
-            seat.done = seat.hand.is_blackjack();
+        for spot in self.seats.iter_mut().flat_map(|seat| seat.hands.iter_mut()) {
+            spot.done = spot.hand.is_blackjack();
+        }
+        let peeked = self.rules.hole_card == HoleCard::Peek;
+        if self.up_card_points() == 11 {
+            self.offer_insurance(events);
+            return self.peek_if_all_insured(events);
+        }
+        if peeked && self.up_card_points() == 10 && self.rules.surrender == Surrender::Early {
+            self.offer_early_surrender(events);
+            return self.peek_if_all_insured(events);
+        }
+        self.check_hole_card(events)
+    }
+
+    fn up_card_points(&self) -> u16 {
+        self.dealer.cards().first().map_or(0, |card| card.rank.points())
+    }
+
+    fn offer_insurance(&mut self, events: &mut Vec<Event>) {
+        self.phase = Phase::Insurance;
+        events.push(Event::InsuranceOffered);
+        let dealt = self.seats.iter_mut().filter(|seat| !seat.hands.is_empty());
+        for seat in dealt {
+            if seat.away {
+                seat.insurance = Some(0);
+                events.push(Event::Insured { name: seat.name.clone(), stake: 0 });
+            } else if seat.hands[0].hand.is_blackjack() {
+                events.push(Event::EvenMoneyOffered { name: seat.name.clone() });
+            }
+        }
+    }
+
+    /// Early surrender has to beat the peek under a ten as well as an ace, so
+    /// the table waits on it the same way it waits on insurance. A natural has
+    /// nothing to surrender, and an away seat keeps its hand.
+    fn offer_early_surrender(&mut self, events: &mut Vec<Event>) {
+        self.phase = Phase::Insurance;
+        events.push(Event::SurrenderOffered);
+        let dealt = self.seats.iter_mut().filter(|seat| !seat.hands.is_empty());
+        for seat in dealt.filter(|seat| seat.away || seat.hands[0].hand.is_blackjack()) {
+            seat.insurance = Some(0);
+            events.push(Event::SurrenderDeclined { name: seat.name.clone() });
+        }
+    }
+
+    /// What the table is asking before the peek: insurance under an ace,
+    /// early surrender under a ten.
+    fn pre_peek_offer(&self) -> Event {
+        match self.up_card_points() {
+            11 => Event::InsuranceOffered,
+            _ => Event::SurrenderOffered,
+        }
+    }
+
+    /// A seat saying no to [`Table::pre_peek_offer`].
+    fn declined(&self, name: &str) -> Event {
+        match self.up_card_points() {
+            11 => Event::Insured { name: name.to_string(), stake: 0 },
+            _ => Event::SurrenderDeclined { name: name.to_string() },
+        }
+    }
+
+    /// Play waits until every dealt seat has answered the insurance offer.
+    /// Like the deal gate, this runs again when a seat leaves.
+    fn peek_if_all_insured(&mut self, events: &mut Vec<Event>) -> Result<(), TableError> {
+        let waiting =
+            self.seats.iter().any(|seat| !seat.hands.is_empty() && seat.insurance.is_none());
+        if self.phase != Phase::Insurance || waiting {
+            return Ok(());
+        }
+        self.phase = Phase::PlayerTurns;
+        self.check_hole_card(events)
+    }
+
+    /// The American peek: under an ace or a ten the dealer looks at the hole
+    /// card before anyone acts, and a natural ends the round on the spot, so
+    /// nobody hits, doubles or splits into a hand they could never beat. It is
+    /// also the only moment insurance can pay. A European dealer has no hole
+    /// card to look at, and everything waits for the end of the round.
+    fn check_hole_card(&mut self, events: &mut Vec<Event>) -> Result<(), TableError> {
+        if self.rules.hole_card == HoleCard::European || self.up_card_points() < 10 {
+            return self.advance_turn(events);
+        }
+        let blackjack = self.dealer.is_blackjack();
+        self.settle_all_insurance(blackjack, events);
+        if blackjack {
+            return self.finish_round(events);
         }
+        events.push(Event::DealerChecked);
         self.advance_turn(events)
     }
 
+    fn settle_all_insurance(&mut self, dealer_blackjack: bool, events: &mut Vec<Event>) {
+        for seat in self.seats.iter_mut().filter(|seat| seat.insurance.unwrap_or(0) > 0) {
+            events.push(settle_insurance(seat, dealer_blackjack));
+        }
+    }
+
     /// Two passes round the table, dealer last, exactly as it is dealt live.
+    /// A European dealer sits out the second pass, and so does any seat that
+    /// did not bet or is sitting out.
     fn deal_opening_cards(&mut self) -> Result<(), TableError> {
-        for _ in 0..2 {
+        for seat in self.seats.iter_mut().filter(|seat| seat.bet > 0 && !seat.sitting_out) {
+            seat.hands = vec![Spot::new(seat.bet)];
+        }
+        for pass in 0..2 {
             for index in 0..self.seats.len() {
+                if self.seats[index].hands.is_empty() {
+                    continue;
+                }
                 let card = self.draw()?;
-                self.seats[index].hand.push(card);
+                self.seats[index].hands[0].hand.push(card);
+            }
+            if pass == 0 || self.rules.hole_card == HoleCard::Peek {
+                let card = self.draw()?;
+                self.dealer.push(card);
             }
-            let card = self.draw()?;
-            self.dealer.push(card);
         }
         Ok(())
     }
 
+    /// Passes the turn to the next open hand, standing every hand of an absent
+    /// seat on the way.
     fn advance_turn(&mut self, events: &mut Vec<Event>) -> Result<(), TableError> {
-        let next = self.seats.iter().find(|seat| !seat.done);
-        let Some((id, name)) = next.map(|seat| (seat.id.clone(), seat.name.clone())) else {
-            return self.finish_round(events);
+        let next = loop {
+            let next = self.seats.iter_mut().find_map(|seat| Some((seat.active()?, seat)));
+            let Some((hand, seat)) = next else {
+                return self.finish_round(events);
+            };
+            if !seat.absent() {
+                break (hand, seat);
+            }
+            seat.hands[hand].done = true;
+            events.push(seat.closed_event(hand));
         };
-        self.turn = Some(id);
-        events.push(Event::TurnStarted { name });
+        let (hand, seat) = next;
+        events.push(Event::TurnStarted { name: seat.name.clone(), hand });
+        self.turn = Some(seat.id.clone());
         Ok(())
     }
 
     fn finish_round(&mut self, events: &mut Vec<Event>) -> Result<(), TableError> {
         self.turn = None;
         self.play_dealer(events)?;
+        // Only side stakes still waiting on a European second card are left:
+        // a dealer that peeked settled its insurance there and then.
+        self.settle_all_insurance(self.dealer.is_blackjack(), events);
         self.settle(events);
         self.reset_for_next_round(events);
         Ok(())
@@ -312,7 +1460,7 @@ impl<R: Rng> Table<R> {
 
     /// The dealer stands pat when every live hand is already bust or a
     /// natural: neither result can change, since a natural is decided by the
-    /// two cards the dealer already holds.
+    /// dealer's first two cards. A European dealer still takes the second.
     fn play_dealer(&mut self, events: &mut Vec<Event>) -> Result<(), TableError> {
         while self.dealer_must_draw() {
             let card = self.draw()?;
@@ -326,27 +1474,56 @@ impl<R: Rng> Table<R> {
     }
 
     fn dealer_must_draw(&self) -> bool {
-        let decided = self.wagered().all(|seat| seat.hand.is_bust() || seat.hand.is_blackjack());
+        if self.dealer.cards().len() < 2 {
+            return true;
+        }
+        let decided = self
+            .wagered()
+            .all(|spot| spot.surrendered || spot.hand.is_bust() || spot.hand.is_blackjack());
         !decided && rules::dealer_should_hit(&self.dealer, self.rules.dealer_hits_soft_17)
     }
 
-    fn wagered(&self) -> impl Iterator<Item = &Seat> + '_ {
-        self.seats.iter().filter(|seat| seat.bet > 0)
+    fn wagered(&self) -> impl Iterator<Item = &Spot> + '_ {
+        self.seats.iter().flat_map(|seat| seat.hands.iter())
     }
 
+    /// Under original-bets-only, a European dealer natural takes each seat's
+    /// opening wager and nothing more: the chips added by doubling or
+    /// splitting go back whatever the outcome.
     fn settle(&mut self, events: &mut Vec<Event>) {
         let dealer = self.dealer.clone();
-        let settled = self.seats.iter_mut().filter(|seat| seat.bet > 0);
-        events.extend(settled.map(|seat| settle_seat(seat, &dealer)));
+        let Rules { surrender, hole_card, original_bets_only, natural_pays, .. } = self.rules;
+        let capped = dealer.is_blackjack() && hole_card == HoleCard::European && original_bets_only;
+        for seat in self.seats.iter_mut() {
+            for hand in 0..seat.hands.len() {
+                let outcome = outcome(&seat.hands[hand], &dealer, surrender);
+                let at_risk = match (capped, hand) {
+                    (false, _) => seat.hands[hand].bet,
+                    (true, 0) => seat.bet,
+                    (true, _) => 0,
+                };
+                events.push(settle_hand(seat, hand, outcome, at_risk, natural_pays));
+            }
+        }
     }
 
     fn reset_for_next_round(&mut self, events: &mut Vec<Event>) {
         self.dealer.clear();
         self.seats.iter_mut().for_each(Seat::clear);
-        let min_bet = self.rules.min_bet;
+        let Rules { min_bet, max_rounds_sat_out, .. } = self.rules;
         let broke = self.seats.iter().filter(|seat| seat.bankroll < min_bet);
-        events.extend(broke.map(|seat| Event::OutOfChips { name: seat.name.clone() }));
+        for seat in broke {
+            events.extend(seat.graded());
+            events.push(Event::OutOfChips { name: seat.name.clone() });
+        }
         self.seats.retain(|seat| seat.bankroll >= min_bet);
+        let squatting =
+            |seat: &Seat| max_rounds_sat_out > 0 && seat.rounds_out >= max_rounds_sat_out;
+        for seat in self.seats.iter().filter(|seat| squatting(seat)) {
+            events.extend(seat.graded());
+            events.push(Event::SeatReleased { name: seat.name.clone(), rounds: seat.rounds_out });
+        }
+        self.seats.retain(|seat| !squatting(seat));
         self.phase = Phase::WaitingForPlayers;
         self.open_betting_if_ready(events);
     }
@@ -367,10 +1544,28 @@ impl<R: Rng> Table<R> {
     /// Cut card at 75% penetration — reshuffle once under a quarter of the
     /// shoe is left, checked between rounds so a shuffle never lands in the
     /// middle of a hand.
-    fn replenish_at_cut_card(&mut self) {
-        if self.deck.len() < 13 * self.rules.packs as usize {
+    fn replenish_at_cut_card(&mut self) -> bool {
+        let cut = self.deck.len() < 13 * self.rules.packs as usize;
+        if cut {
             self.replenish();
         }
+        cut
+    }
+
+    /// A trainer asks every seat still at the table for the count of the
+    /// shoe just finished before it goes. The count has not seen this call's
+    /// events yet, so a copy catches up on them first.
+    fn announce_shuffle(&mut self, events: &mut Vec<Event>) {
+        if let Some(count) = &self.trainer {
+            let mut count = count.clone();
+            events.iter().for_each(|event| count.observe(event));
+            let reading = count.reading();
+            for seat in self.seats.iter_mut().filter(|seat| !seat.away) {
+                seat.quiz = Some(reading);
+            }
+            events.push(Event::CountAsked { true_count: reading.true_count.is_some() });
+        }
+        events.push(Event::Shuffled);
     }
 
     fn replenish(&mut self) {
@@ -379,12 +1574,17 @@ impl<R: Rng> Table<R> {
     }
 }
 
-fn check_wager(seat: &Seat, amount: u32, min_bet: u32) -> Result<(), TableError> {
+/// The table limits bound the opening wager only. Doubling and splitting match
+/// a wager that was already legal, so a seat at the maximum can still do both.
+fn check_wager(seat: &Seat, amount: u32, rules: &Rules) -> Result<(), TableError> {
     if seat.bet > 0 {
         return Err(TableError::AlreadyBet);
     }
-    if amount < min_bet {
-        return Err(TableError::BetTooSmall { min_bet });
+    if amount < rules.min_bet {
+        return Err(TableError::BetTooSmall { min_bet: rules.min_bet });
+    }
+    if amount > rules.max_bet {
+        return Err(TableError::BetTooLarge { max_bet: rules.max_bet });
     }
     if amount > seat.bankroll {
         return Err(TableError::InsufficientChips { bankroll: seat.bankroll });
@@ -392,24 +1592,44 @@ fn check_wager(seat: &Seat, amount: u32, min_bet: u32) -> Result<(), TableError>
     Ok(())
 }
 
-fn hand_closed(name: String, total: Total) -> Event {
-    if total.value > 21 {
-        Event::Bust { name, total }
-    } else {
-        Event::Stood { name, total }
+fn settle_insurance(seat: &mut Seat, dealer_blackjack: bool) -> Event {
+    let stake = seat.insurance.unwrap_or(0);
+    let payout = rules::insurance_payout(stake, dealer_blackjack);
+    seat.bankroll = seat.bankroll.saturating_add(payout);
+    seat.insurance = Some(0);
+    Event::InsuranceSettled { name: seat.name.clone(), stake, payout, bankroll: seat.bankroll }
+}
+
+fn outcome(spot: &Spot, dealer: &Hand, surrender: Surrender) -> Outcome {
+    if spot.even_money {
+        return Outcome::EvenMoney;
+    }
+    if spot.surrendered {
+        return rules::resolve_surrender(dealer, surrender);
     }
+    rules::resolve(&spot.hand, dealer)
 }
 
-fn settle_seat(seat: &mut Seat, dealer: &Hand) -> Event {
-    let outcome = rules::resolve(&seat.hand, dealer);
-    let payout = rules::payout(outcome, seat.bet);
-    seat.bankroll += payout;
-    Event::Settled { name: seat.name.clone(), outcome, payout, bankroll: seat.bankroll }
+/// Only `at_risk` of the hand's stake rides on the outcome; any rest of it is
+/// handed back. A bankroll won past `u32::MAX` chips stops there rather than
+/// wrapping round to nothing.
+fn settle_hand(
+    seat: &mut Seat,
+    hand: usize,
+    outcome: Outcome,
+    at_risk: u32,
+    natural_pays: Odds,
+) -> Event {
+    let payout = seat.hands[hand].bet - at_risk + rules::payout(outcome, at_risk, natural_pays);
+    seat.bankroll = seat.bankroll.saturating_add(payout);
+    Event::Settled { name: seat.name.clone(), hand, outcome, payout, bankroll: seat.bankroll }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::card::Rank::{self, *};
+    use crate::card::Suit;
     use rand::rngs::StdRng;
     use rand::SeedableRng;
 
@@ -418,8 +1638,17 @@ mod tests {
             packs: 6,
             dealer_hits_soft_17: false,
             min_bet: 5,
+            max_bet: 500,
             starting_bankroll: 100,
             min_players: 1,
+            max_rounds_sat_out: 5,
+            max_resplits: 2,
+            split_aces_one_card: true,
+            split_ace_21_is_natural: false,
+            surrender: Surrender::None,
+            hole_card: HoleCard::Peek,
+            original_bets_only: false,
+            natural_pays: Odds::THREE_TO_TWO,
         }
     }
 
@@ -435,8 +1664,20 @@ mod tests {
         table
     }
 
+    /// Seats `ids` over a shoe that deals `draws` first, in table order: each
+    /// seat then the dealer, twice round, then whatever the players ask for.
+    /// A fresh pack sits underneath so the cut card is never reached.
+    fn rigged(rules: Rules, ids: &[&str], draws: &[Rank]) -> Table<StdRng> {
+        let mut table = seated(Rules { packs: 1, ..rules }, ids);
+        let pack = Suit::ALL.iter().flat_map(|&suit| Rank::ALL.map(|rank| Card { rank, suit }));
+        let stacked = draws.iter().rev().map(|&rank| Card { rank, suit: Suit::Clubs });
+        table.deck = Deck::from_cards(pack.chain(stacked).collect());
+        table
+    }
+
     fn play_to_settlement(table: &mut Table<StdRng>, id: &str) -> Vec<Event> {
         let mut events = table.apply(id, Command::Bet(10)).expect("bet accepted");
+        events.extend(decline_insurance(table, &[id]));
         while table.phase() == Phase::PlayerTurns {
             events.extend(table.apply(id, Command::Stand).expect("stand accepted"));
         }
@@ -448,6 +1689,16 @@ mod tests {
     /// because the table quietly dropped the players out from under them.
     const DEEP_POCKETS: u32 = 3_000;
 
+    /// An ace up pauses every seeded round that draws one; these tests are
+    /// about what comes after, so every seat simply says no.
+    fn decline_insurance(table: &mut Table<StdRng>, ids: &[&str]) -> Vec<Event> {
+        if table.phase() != Phase::Insurance {
+            return Vec::new();
+        }
+        let declined = ids.iter().map(|id| table.apply(id, Command::Insure(0)));
+        declined.flat_map(|events| events.expect("every seat is asked")).collect()
+    }
+
     fn settled_outcome(events: &[Event]) -> Option<Outcome> {
         events.iter().find_map(|event| match event {
             Event::Settled { outcome, .. } => Some(*outcome),
@@ -476,6 +1727,18 @@ mod tests {
         let refs: Vec<&str> = ids.iter().map(String::as_str).collect();
         let mut table = seated(rules(), &refs);
         assert_eq!(table.join("late", "Late"), Err(TableError::TableFull));
+        assert_eq!(table.occupancy(), MAX_SEATS);
+    }
+
+    #[test]
+    fn a_returning_player_sits_down_with_their_own_chips() {
+        let mut table = table(rules());
+        let events = table.join_with_bankroll("a", "Ada", 340).expect("seat free");
+        assert_eq!(events[0], Event::Joined { name: "Ada".into(), bankroll: 340, bot: false });
+        assert_eq!(table.bankroll("a"), Some(340));
+        let error = table.join_with_bankroll("b", "Bo", 4).unwrap_err();
+        assert_eq!(error, TableError::InsufficientChips { bankroll: 4 });
+        assert!(!table.is_seated("b"));
     }
 
     #[test]
@@ -493,6 +1756,24 @@ mod tests {
         assert_eq!(error, TableError::InsufficientChips { bankroll: 100 });
     }
 
+    #[test]
+    fn a_bet_above_the_table_maximum_is_rejected_even_when_the_bankroll_covers_it() {
+        let mut table = seated(Rules { max_bet: 50, ..rules() }, &["a"]);
+        let error = table.apply("a", Command::Bet(51)).unwrap_err();
+        assert_eq!(error, TableError::BetTooLarge { max_bet: 50 });
+        assert_eq!(table.bankroll("a"), Some(100));
+        assert!(table.apply("a", Command::Bet(50)).is_ok(), "the maximum itself is legal");
+    }
+
+    #[test]
+    fn doubling_a_maximum_bet_is_still_allowed() {
+        let mut table =
+            rigged(Rules { max_bet: 50, ..rules() }, &["a"], &[Six, Ten, Five, Seven, Two]);
+        table.apply("a", Command::Bet(50)).expect("bet at the maximum");
+        let events = table.apply("a", Command::Double).expect("a double is not a new bet");
+        assert_eq!(events[0], Event::Doubled { name: "a".into(), hand: 0, stake: 100 });
+    }
+
     #[test]
     fn a_wager_is_taken_from_the_bankroll_exactly_once() {
         let mut table = seated(rules(), &["a", "b"]);
@@ -539,8 +1820,9 @@ mod tests {
         let events = play_to_settlement(&mut table, "a");
         let expected = match settled_outcome(&events).expect("settled") {
             Outcome::Blackjack => 115,
-            Outcome::Win => 110,
+            Outcome::EvenMoney | Outcome::Win => 110,
             Outcome::Push => 100,
+            Outcome::Surrender => 95,
             Outcome::Lose => 90,
         };
         assert_eq!(table.bankroll("a"), Some(expected));
@@ -566,6 +1848,7 @@ mod tests {
         for id in ["a", "b"] {
             events.extend(table.apply(id, Command::Bet(5)).expect("every seat can cover 5"));
         }
+        events.extend(decline_insurance(table, &["a", "b"]));
         while table.phase() == Phase::PlayerTurns {
             ["a", "b"].into_iter().for_each(|id| events.extend(stand(table, id)));
         }
@@ -606,6 +1889,307 @@ mod tests {
         assert_eq!(table.phase(), Phase::Betting, "the next round is open");
     }
 
+    #[test]
+    fn a_dropped_player_on_their_turn_is_stood_and_the_turn_moves_on() {
+        let mut table = rigged(rules(), &["a", "b"], &[Ten, Nine, Seven, Eight, Nine, Ten]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("b", Command::Bet(10)).expect("bet accepted");
+        let events = table.step_away("a").expect("the seat is held");
+        assert_eq!(events[0], Event::SteppedAway { name: "a".into() });
+        assert_eq!(events[1], Event::Stood { name: "a".into(), hand: 0, total: total(18) });
+        assert_eq!(events[2], Event::TurnStarted { name: "b".into(), hand: 0 });
+        assert!(table.is_seated("a"), "the seat is held, not given up");
+    }
+
+    #[test]
+    fn an_away_seat_is_stood_when_the_turn_reaches_it_later() {
+        let mut table = rigged(rules(), &["a", "b"], &[Ten, Nine, Seven, Eight, Nine, Ten]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("b", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(table.step_away("b").expect("held"), [Event::SteppedAway { name: "b".into() }]);
+        let events = table.apply("a", Command::Stand).expect("a stands");
+        assert!(events.contains(&Event::Stood { name: "b".into(), hand: 0, total: total(18) }));
+        assert_eq!(settlements(&events), 2, "the round settled without waiting on b");
+    }
+
+    #[test]
+    fn an_away_seat_sits_out_betting_instead_of_holding_up_the_deal() {
+        let mut table = seated(rules(), &["a", "b"]);
+        table.step_away("b").expect("held");
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert_ne!(table.phase(), Phase::Betting, "the deal must not wait on b");
+        assert!(table.summary("b").iter().all(|event| !matches!(
+            event,
+            Event::Dealt { name, .. } if name == "b"
+        )));
+        assert_eq!(table.bankroll("b"), Some(100));
+    }
+
+    #[test]
+    fn an_away_seat_declines_insurance_so_the_peek_goes_ahead() {
+        let mut table = rigged(rules(), &["a", "b"], &[Ten, Nine, Ace, Eight, Nine, Seven]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("b", Command::Bet(10)).expect("bet accepted");
+        table.apply("a", Command::Insure(0)).expect("a declines");
+        let events = table.step_away("b").expect("held");
+        assert!(events.contains(&Event::Insured { name: "b".into(), stake: 0 }));
+        assert!(events.contains(&Event::DealerChecked));
+        assert_eq!(table.phase(), Phase::PlayerTurns);
+    }
+
+    #[test]
+    fn a_resumed_seat_keeps_its_chips_and_cards_under_the_new_connection() {
+        let mut table = rigged(rules(), &["a", "b"], &[Ten, Nine, Seven, Eight, Nine, Ten]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("b", Command::Bet(10)).expect("bet accepted");
+        table.step_away("a").expect("held");
+        let events = table.resume("a", "a2").expect("the seat was held");
+        assert_eq!(events, [Event::Returned { name: "a".into(), bankroll: 90 }]);
+        assert!(table.is_seated("a2") && !table.is_seated("a"));
+        assert_eq!(table.leave("a"), Ok(Vec::new()), "the old connection no longer owns it");
+        let summary = table.summary("a2");
+        assert!(matches!(summary[0], Event::DealerShows { hole_card: true, .. }));
+        assert!(summary.contains(&Event::Dealt {
+            name: "a".into(),
+            hand: 0,
+            cards: vec![
+                Card { rank: Ten, suit: Suit::Clubs },
+                Card { rank: Eight, suit: Suit::Clubs }
+            ],
+            total: total(18),
+        }));
+        assert_eq!(summary.last(), Some(&Event::TurnStarted { name: "b".into(), hand: 0 }));
+        assert_eq!(table.resume("gone", "a3"), Err(TableError::UnknownSeat));
+    }
+
+    const CLOCKS: Clocks =
+        Clocks { betting: Duration::from_secs(20), decision: Duration::from_secs(10) };
+
+    #[test]
+    fn an_expired_turn_is_stood_and_the_turn_moves_on() {
+        let mut table = rigged(rules(), &["a", "b"], &[Ten, Nine, Seven, Eight, Nine, Ten]);
+        table.clocks = CLOCKS;
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("b", Command::Bet(10)).expect("bet accepted");
+        let start = Instant::now();
+        assert!(table.tick(start).expect("the clock starts").is_empty());
+        assert!(table.tick(start + Duration::from_secs(9)).expect("still thinking").is_empty());
+        let events = table.tick(start + Duration::from_secs(10)).expect("time is up");
+        assert_eq!(events[0], Event::TurnTimedOut { name: "a".into() });
+        assert_eq!(events[1], Event::Stood { name: "a".into(), hand: 0, total: total(18) });
+        assert_eq!(events[2], Event::TurnStarted { name: "b".into(), hand: 0 });
+    }
+
+    #[test]
+    fn every_hit_gets_a_fresh_decision_clock() {
+        let draws = [Two, Nine, Three, Eight, Nine, Ten, Two];
+        let mut table = rigged(rules(), &["a", "b"], &draws);
+        table.clocks = CLOCKS;
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("b", Command::Bet(10)).expect("bet accepted");
+        let start = Instant::now();
+        table.tick(start).expect("the clock starts");
+        table.apply("a", Command::Hit).expect("a hits with seconds to spare");
+        assert!(table.tick(start + Duration::from_secs(9)).expect("a new clock").is_empty());
+        assert!(table.tick(start + Duration::from_secs(18)).expect("still thinking").is_empty());
+        let events = table.tick(start + Duration::from_secs(19)).expect("time is up");
+        assert_eq!(events[0], Event::TurnTimedOut { name: "a".into() });
+    }
+
+    #[test]
+    fn a_seat_that_does_not_bet_in_time_sits_the_round_out() {
+        let mut table = seated(rules(), &["a", "b"]).with_clocks(CLOCKS);
+        let start = Instant::now();
+        table.tick(start).expect("the clock starts");
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        let events = table.tick(start + Duration::from_secs(20)).expect("time is up");
+        assert_eq!(events[0], Event::SatOut { name: "b".into() });
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, Event::Dealt { name, .. } if name == "a")));
+        assert_ne!(table.phase(), Phase::Betting, "the deal did not wait on b");
+        assert_eq!(table.bankroll("b"), Some(100), "b keeps the seat and the chips");
+    }
+
+    #[test]
+    fn an_unanswered_insurance_offer_is_declined_when_the_clock_runs_out() {
+        let mut table = rigged(rules(), &["a", "b"], &[Ten, Nine, Ace, Eight, Nine, Seven]);
+        table.clocks = CLOCKS;
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("b", Command::Bet(10)).expect("bet accepted");
+        table.apply("a", Command::Insure(0)).expect("a declines");
+        let start = Instant::now();
+        table.tick(start).expect("the clock starts");
+        let events = table.tick(start + Duration::from_secs(10)).expect("time is up");
+        assert_eq!(events[0], Event::TurnTimedOut { name: "b".into() });
+        assert_eq!(events[1], Event::Insured { name: "b".into(), stake: 0 });
+        assert!(events.contains(&Event::DealerChecked));
+    }
+
+    #[test]
+    fn a_table_without_clocks_waits_forever() {
+        let mut table = seated(rules(), &["a", "b"]);
+        let start = Instant::now();
+        table.tick(start).expect("no clocks to start");
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        let events = table.tick(start + Duration::from_secs(86_400)).expect("nothing to do");
+        assert!(events.is_empty());
+        assert_eq!(table.phase(), Phase::Betting);
+    }
+
+    #[test]
+    fn a_seat_resumed_on_its_own_turn_plays_it_from_the_new_connection() {
+        let mut table = rigged(rules(), &["a", "b"], &[Ten, Nine, Seven, Eight, Nine, Ten]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("b", Command::Bet(10)).expect("bet accepted");
+        table.resume("a", "a2").expect("the seat is there to take");
+        assert_eq!(table.apply("a", Command::Stand), Err(TableError::NotYourTurn));
+        let events = table.apply("a2", Command::Stand).expect("the turn came along");
+        assert_eq!(events.last(), Some(&Event::TurnStarted { name: "b".into(), hand: 0 }));
+        assert_eq!(table.step_away("a"), Ok(Vec::new()), "the old connection owns nothing");
+    }
+
+    #[test]
+    fn a_seat_sitting_out_keeps_its_chips_and_is_not_dealt_in() {
+        let mut table = seated(rules(), &["a", "b"]);
+        assert_eq!(table.apply("b", Command::SitOut), Ok(vec![Event::SatOut { name: "b".into() }]));
+        let events = table.apply("a", Command::Bet(10)).expect("the deal does not wait on b");
+        assert!(events
+            .iter()
+            .all(|event| !matches!(event, Event::Dealt { name, .. } if name == "b")));
+        decline_insurance(&mut table, &["a"]);
+        while table.phase() == Phase::PlayerTurns {
+            table.apply("a", Command::Stand).expect("only a is dealt in");
+        }
+        assert_eq!(table.bankroll("b"), Some(100));
+        assert_eq!(table.apply("b", Command::SitOut), Err(TableError::AlreadySittingOut));
+    }
+
+    #[test]
+    fn a_wager_already_down_has_to_be_played() {
+        let mut table = seated(rules(), &["a", "b"]);
+        table.apply("b", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(table.apply("b", Command::SitOut), Err(TableError::AlreadyBet));
+        assert_eq!(table.apply("a", Command::SitIn), Err(TableError::NotSittingOut));
+    }
+
+    #[test]
+    fn betting_from_a_seat_sitting_out_sits_it_back_in() {
+        let mut table = seated(rules(), &["a", "b"]);
+        table.apply("b", Command::SitOut).expect("b sits out");
+        let events = table.apply("b", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(events[0], Event::SatIn { name: "b".into() });
+        assert_eq!(events[1], Event::BetPlaced { name: "b".into(), amount: 10 });
+        assert_eq!(table.phase(), Phase::Betting, "the table waits on a again");
+    }
+
+    #[test]
+    fn sitting_out_on_your_turn_stands_the_hand_and_moves_on() {
+        let mut table = rigged(rules(), &["a", "b"], &[Ten, Nine, Seven, Eight, Nine, Ten]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("b", Command::Bet(10)).expect("bet accepted");
+        let events = table.apply("a", Command::SitOut).expect("a sits out");
+        assert_eq!(events[1], Event::Stood { name: "a".into(), hand: 0, total: total(18) });
+        assert_eq!(events[2], Event::TurnStarted { name: "b".into(), hand: 0 });
+    }
+
+    #[test]
+    fn a_seat_that_sits_out_too_many_rounds_in_a_row_is_given_up() {
+        let mut table = seated(Rules { max_rounds_sat_out: 2, ..rules() }, &["a", "b"]);
+        table.apply("b", Command::SitOut).expect("b sits out");
+        let mut events = Vec::new();
+        for _ in 0..2 {
+            events.extend(play_to_settlement(&mut table, "a"));
+        }
+        let released = Event::SeatReleased { name: "b".into(), rounds: 2 };
+        assert_eq!(events.iter().filter(|&event| *event == released).count(), 1);
+        assert!(!table.is_seated("b"));
+        assert!(table.is_seated("a"));
+    }
+
+    #[test]
+    fn sitting_back_in_starts_the_count_again() {
+        let mut table = seated(Rules { max_rounds_sat_out: 2, ..rules() }, &["a", "b"]);
+        table.apply("b", Command::SitOut).expect("b sits out");
+        play_to_settlement(&mut table, "a");
+        table.apply("b", Command::SitIn).expect("b sits in");
+        table.apply("b", Command::SitOut).expect("b sits out again");
+        play_to_settlement(&mut table, "a");
+        assert!(table.is_seated("b"), "one round out, twice, is not two in a row");
+    }
+
+    #[test]
+    fn a_spectator_watches_without_holding_a_seat_or_acting() {
+        let mut table = seated(rules(), &["a"]);
+        assert_eq!(table.watch("w", "Wes", 80), Ok(vec![Event::Watching { name: "Wes".into() }]));
+        assert!(table.is_watching("w") && !table.is_seated("w"));
+        assert_eq!((table.occupancy(), table.audience()), (1, 1));
+        assert_eq!(table.apply("w", Command::Bet(10)), Err(TableError::UnknownSeat));
+        assert_eq!(table.watch("w", "Wes", 80), Err(TableError::AlreadySeated));
+        assert_eq!((table.name_of("w"), table.name_of("a")), (Some("Wes"), Some("a")));
+        assert_eq!(table.name_of("nobody"), None);
+    }
+
+    #[test]
+    fn a_spectator_is_shown_the_felt_but_not_asked_to_bet() {
+        let mut table = rigged(rules(), &["a"], &[Ten, Nine, Eight, Seven]);
+        table.watch("w", "Wes", 80).expect("room to watch");
+        assert!(table.summary("w").is_empty(), "nothing on the felt during betting");
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        let summary = table.summary("w");
+        assert!(matches!(summary[0], Event::DealerShows { hole_card: true, .. }));
+        assert_eq!(summary.last(), Some(&Event::TurnStarted { name: "a".into(), hand: 0 }));
+    }
+
+    #[test]
+    fn the_gallery_holds_at_most_sixteen_spectators() {
+        let mut table = seated(rules(), &["a"]);
+        for id in 0..MAX_SPECTATORS {
+            table.watch(&id.to_string(), "fan", 0).expect("room to watch");
+        }
+        assert_eq!(table.watch("late", "fan", 0), Err(TableError::GalleryFull));
+    }
+
+    #[test]
+    fn a_spectator_sits_down_as_themselves_when_a_seat_is_free() {
+        let ids = ["a", "b", "c", "d", "e", "f", "g"];
+        let mut table = seated(rules(), &ids);
+        table.watch("w", "Wes", 80).expect("room to watch");
+        assert_eq!(table.apply("w", Command::Sit), Err(TableError::TableFull));
+        assert!(table.is_watching("w"), "a full table leaves them watching");
+        table.leave("g").expect("a seat frees up");
+        let events = table.apply("w", Command::Sit).expect("the seat is free");
+        assert_eq!(events, [Event::Joined { name: "Wes".into(), bankroll: 80, bot: false }]);
+        assert!(table.is_seated("w") && !table.is_watching("w"));
+        assert_eq!(table.apply("w", Command::Sit), Err(TableError::AlreadySeated));
+    }
+
+    #[test]
+    fn a_bot_gives_its_seat_to_a_player_and_is_not_counted_as_one() {
+        let mut table = seated(rules(), &["a", "b", "c", "d", "e"]);
+        table.join_bot("bot-1", "Bot 1").expect("seat is free");
+        table.join_bot("bot-2", "Bot 2").expect("seat is free");
+        assert_eq!((table.occupancy(), table.playing()), (5, 5));
+        assert_eq!(table.join_bot("bot-3", "Bot 3"), Err(TableError::TableFull));
+
+        let events = table.join("f", "Fay").expect("a bot makes way");
+        assert!(matches!(&events[0], Event::Left { name, .. } if name == "Bot 2"));
+        assert!(!table.is_seated("bot-2") && table.is_seated("bot-1"));
+        table.watch("w", "Wes", 80).expect("room to watch");
+        table.apply("w", Command::Sit).expect("the last bot makes way");
+        assert!(!table.is_seated("bot-1"));
+        assert_eq!(table.join("late", "Late"), Err(TableError::TableFull));
+    }
+
+    #[test]
+    fn a_spectator_who_drops_stops_watching_at_once() {
+        let mut table = seated(rules(), &["a"]);
+        table.watch("w", "Wes", 80).expect("room to watch");
+        let events = table.step_away("w").expect("nothing to hold");
+        assert_eq!(events, [Event::StoppedWatching { name: "Wes".into() }]);
+        assert_eq!(table.audience(), 0);
+    }
+
     #[test]
     fn the_last_player_leaving_returns_the_table_to_waiting() {
         let mut table = seated(rules(), &["a"]);
@@ -625,6 +2209,7 @@ mod tests {
         let broke = Rules { min_bet: 100, starting_bankroll: 100, ..rules() };
         let mut table = seated(broke, &["a"]);
         let mut events = table.apply("a", Command::Bet(100)).expect("bet accepted");
+        events.extend(decline_insurance(&mut table, &["a"]));
         while table.phase() == Phase::PlayerTurns {
             events.extend(table.apply("a", Command::Stand).expect("stand accepted"));
         }
@@ -641,6 +2226,7 @@ mod tests {
         let mut settled = 0;
         for round in 0..200 {
             let mut events = table.apply("a", Command::Bet(5)).expect("the shoe is never dry");
+            events.extend(decline_insurance(&mut table, &["a"]));
             while table.phase() == Phase::PlayerTurns {
                 events.extend(table.apply("a", Command::Hit).expect("hit accepted"));
             }
@@ -649,4 +2235,385 @@ mod tests {
         }
         assert_eq!(settled, 200, "every round dealt from a shoe that was rebuilt in place");
     }
+
+    #[test]
+    fn doubling_takes_a_second_wager_and_closes_the_hand_after_one_card() {
+        let mut table = rigged(rules(), &["a"], &[Six, Ten, Five, Seven, Ten]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        let events = table.apply("a", Command::Double).expect("double on eleven");
+        assert_eq!(events[0], Event::Doubled { name: "a".into(), hand: 0, stake: 20 });
+        assert!(events.contains(&Event::Stood { name: "a".into(), hand: 0, total: total(21) }));
+        assert_eq!(settled_outcome(&events), Some(Outcome::Win));
+        assert_eq!(table.bankroll("a"), Some(120), "a doubled win pays on the whole stake");
+    }
+
+    #[test]
+    fn doubling_after_a_hit_is_rejected() {
+        let mut table = rigged(rules(), &["a"], &[Two, Ten, Three, Seven, Four]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("a", Command::Hit).expect("hit on five");
+        assert_eq!(table.apply("a", Command::Double), Err(TableError::NotOnTwoCards));
+    }
+
+    #[test]
+    fn doubling_without_the_chips_for_a_second_wager_is_rejected() {
+        let mut table = rigged(rules(), &["a"], &[Six, Ten, Five, Seven]);
+        table.apply("a", Command::Bet(60)).expect("bet accepted");
+        let error = table.apply("a", Command::Double).unwrap_err();
+        assert_eq!(error, TableError::CannotAffordDouble { bankroll: 40 });
+        assert_eq!(table.bankroll("a"), Some(40), "a refused double takes nothing");
+    }
+
+    #[test]
+    fn a_split_pair_is_played_left_to_right_with_a_wager_on_each_hand() {
+        let mut table = rigged(rules(), &["a"], &[Eight, Ten, Eight, Seven, Three, Ten]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        let events = table.apply("a", Command::Split).expect("eights split");
+        assert_eq!(events[0], Event::Split { name: "a".into(), hand: 0, stake: 10 });
+        assert_eq!(table.bankroll("a"), Some(80));
+        let events = table.apply("a", Command::Stand).expect("first hand stands on 11");
+        assert_eq!(events.last(), Some(&Event::TurnStarted { name: "a".into(), hand: 1 }));
+        let events = table.apply("a", Command::Stand).expect("second hand stands on 18");
+        let outcomes: Vec<(usize, Outcome)> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Settled { hand, outcome, .. } => Some((*hand, *outcome)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(outcomes, [(0, Outcome::Lose), (1, Outcome::Win)]);
+        assert_eq!(table.bankroll("a"), Some(100));
+    }
+
+    #[test]
+    fn split_aces_take_one_card_each_and_twenty_one_is_not_a_natural() {
+        let mut table = rigged(rules(), &["a"], &[Ace, Nine, Ace, Eight, King, Queen]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        let events = table.apply("a", Command::Split).expect("aces split");
+        assert_eq!(settlements(&events), 2, "both hands closed on their one card");
+        assert!(events
+            .iter()
+            .all(|event| !matches!(event, Event::Settled { outcome: Outcome::Blackjack, .. })));
+        assert_eq!(table.bankroll("a"), Some(120));
+    }
+
+    #[test]
+    fn split_aces_can_be_paid_as_naturals_when_the_table_says_so() {
+        let generous = Rules { split_ace_21_is_natural: true, ..rules() };
+        let mut table = rigged(generous, &["a"], &[Ace, Nine, Ace, Eight, King, Queen]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("a", Command::Split).expect("aces split");
+        assert_eq!(table.bankroll("a"), Some(130));
+    }
+
+    #[test]
+    fn a_seat_cannot_split_past_the_resplit_limit() {
+        let one_split = Rules { max_resplits: 0, ..rules() };
+        let mut table = rigged(one_split, &["a"], &[Eight, Ten, Eight, Seven, Eight, Two]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("a", Command::Split).expect("the first split is allowed");
+        assert_eq!(table.apply("a", Command::Split), Err(TableError::SplitLimit { hands: 2 }));
+        assert_eq!(table.bankroll("a"), Some(80), "a refused split takes nothing");
+    }
+
+    #[test]
+    fn only_a_pair_can_be_split() {
+        let mut table = rigged(rules(), &["a"], &[Eight, Ten, Nine, Seven]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(table.apply("a", Command::Split), Err(TableError::NotAPair));
+    }
+
+    #[test]
+    fn an_ace_up_opens_insurance_and_a_dealer_natural_pays_it_two_to_one() {
+        let mut table = rigged(rules(), &["a"], &[Ten, Ace, Nine, King]);
+        let events = table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert!(events.contains(&Event::InsuranceOffered));
+        assert_eq!(table.phase(), Phase::Insurance);
+        assert_eq!(table.apply("a", Command::Hit), Err(TableError::WrongPhase));
+        let events = table.apply("a", Command::Insure(5)).expect("half the wager");
+        let insurance =
+            Event::InsuranceSettled { name: "a".into(), stake: 5, payout: 15, bankroll: 100 };
+        assert!(events.contains(&insurance));
+        assert_eq!(settled_outcome(&events), Some(Outcome::Lose), "the round ends at the peek");
+        assert_eq!(table.bankroll("a"), Some(100), "insurance covered the lost wager");
+    }
+
+    #[test]
+    fn insurance_is_lost_when_the_dealer_checks_and_play_continues() {
+        let mut table = rigged(rules(), &["a"], &[Ten, Ace, Nine, Seven]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        let events = table.apply("a", Command::Insure(4)).expect("under half the wager");
+        assert!(events.contains(&Event::DealerChecked));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Event::InsuranceSettled { stake: 4, payout: 0, bankroll: 86, .. }
+        )));
+        assert_eq!(table.phase(), Phase::PlayerTurns);
+    }
+
+    #[test]
+    fn insurance_is_capped_at_half_the_wager_and_taken_only_once() {
+        let mut table = rigged(rules(), &["a", "b"], &[Ten, Ten, Ace, Nine, Nine, Seven]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("b", Command::Bet(10)).expect("bet accepted");
+        let error = table.apply("a", Command::Insure(6)).unwrap_err();
+        assert_eq!(error, TableError::InsuranceTooLarge { max: 5 });
+        table.apply("a", Command::Insure(0)).expect("declining is an answer");
+        assert_eq!(table.apply("a", Command::Insure(5)), Err(TableError::InsuranceDecided));
+    }
+
+    #[test]
+    fn a_natural_can_take_even_money_against_a_dealer_natural() {
+        let mut table = rigged(rules(), &["a"], &[Ace, Ace, King, King]);
+        let events = table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert!(events.contains(&Event::EvenMoneyOffered { name: "a".into() }));
+        let events = table.apply("a", Command::EvenMoney).expect("a holds a natural");
+        assert_eq!(settled_outcome(&events), Some(Outcome::EvenMoney));
+        assert_eq!(table.bankroll("a"), Some(110), "a push became a 1:1 win");
+    }
+
+    #[test]
+    fn even_money_is_refused_without_a_natural() {
+        let mut table = rigged(rules(), &["a"], &[Ten, Ace, Nine, Seven]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(table.apply("a", Command::EvenMoney), Err(TableError::NoNatural));
+    }
+
+    #[test]
+    fn a_player_leaving_during_insurance_does_not_wedge_the_peek() {
+        let mut table = rigged(rules(), &["a", "b"], &[Ten, Ten, Ace, Nine, Nine, Seven]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("b", Command::Bet(10)).expect("bet accepted");
+        table.apply("a", Command::Insure(0)).expect("a declines");
+        table.leave("b").expect("the round moves on");
+        assert_eq!(table.phase(), Phase::PlayerTurns, "the peek must not need b");
+    }
+
+    #[test]
+    fn late_surrender_gives_back_half_the_wager_and_closes_the_hand() {
+        let late = Rules { surrender: Surrender::Late, ..rules() };
+        let mut table = rigged(late, &["a"], &[Ten, Nine, Six, Eight]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        let events = table.apply("a", Command::Surrender).expect("first decision");
+        assert_eq!(events[0], Event::Surrendered { name: "a".into() });
+        assert_eq!(settled_outcome(&events), Some(Outcome::Surrender));
+        assert_eq!(table.bankroll("a"), Some(95));
+    }
+
+    #[test]
+    fn surrender_after_a_hit_or_at_a_table_without_it_is_refused() {
+        let mut table = rigged(rules(), &["a"], &[Ten, Nine, Two, Eight]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(table.apply("a", Command::Surrender), Err(TableError::SurrenderNotOffered));
+
+        let late = Rules { surrender: Surrender::Late, ..rules() };
+        let mut table = rigged(late, &["a"], &[Ten, Nine, Two, Eight, Two]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("a", Command::Hit).expect("hit on twelve");
+        assert_eq!(table.apply("a", Command::Surrender), Err(TableError::TooLateToSurrender));
+    }
+
+    #[test]
+    fn a_hint_answers_for_the_hand_to_play_with_the_moves_it_has() {
+        let late = Rules { surrender: Surrender::Late, ..rules() };
+        let mut table = rigged(late, &["a"], &[Ten, Ten, Six, Seven, Two]);
+        assert_eq!(table.hint("a"), Err(TableError::WrongPhase));
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(table.hint("a"), Ok((0, Action::Surrender)));
+        table.apply("a", Command::Hit).expect("hit on sixteen");
+        assert_eq!(table.hint("a"), Ok((0, Action::Stand)), "surrender is gone after a hit");
+    }
+
+    #[test]
+    fn moves_are_graded_quietly_and_the_grade_is_told_when_the_seat_is_given_up() {
+        let mut table = rigged(rules(), &["a"], &[Ten, Six, Nine, Two, Ace]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(table.apply("a", Command::Split), Err(TableError::NotAPair));
+        let events = table.apply("a", Command::Hit).expect("hit on nineteen against a six");
+        assert!(events.iter().all(|event| !matches!(event, Event::Graded { .. })));
+        table.apply("a", Command::Stand).expect("stand on twenty");
+        let accuracy = table.accuracy("a").expect("seated");
+        assert_eq!((accuracy.decisions, accuracy.mistakes), (2, 1), "the refused split is not one");
+        assert!(accuracy.ev_lost > 0.0);
+
+        let events = table.leave("a").expect("leave");
+        assert_eq!(
+            events[..2],
+            [Event::Graded { name: "a".into(), accuracy }, Event::Left { name: "a".into() }]
+        );
+        assert_eq!(table.accuracy("a"), Err(TableError::UnknownSeat));
+    }
+
+    #[test]
+    fn a_trainer_asks_for_the_count_of_what_has_been_shown_and_marks_the_answer_once() {
+        assert_eq!(rigged(rules(), &["a"], &[]).quiz("a"), Err(TableError::NotATrainer));
+        let mut table =
+            rigged(rules(), &["a"], &[Two, Five, Nine, Seven]).with_trainer(count::HI_LO);
+        assert_eq!(table.answer_count("a", 0, Some(0)), Err(TableError::NothingAsked));
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(table.quiz("a"), Ok(Event::CountAsked { true_count: true }));
+        let verdict = table.answer_count("a", 2, Some(2)).expect("just asked");
+        assert_eq!(verdict.reading.running, 2, "the hole card is not counted");
+        assert_eq!((verdict.running_right, verdict.true_right), (true, Some(true)));
+        assert_eq!(table.answer_count("a", 2, Some(2)), Err(TableError::NothingAsked));
+        assert_eq!(table.quiz("b"), Err(TableError::UnknownSeat));
+    }
+
+    #[test]
+    fn a_trainer_counts_every_card_of_a_seat_that_left_mid_round_and_came_back() {
+        let draws = [Two, Five, Nine, Seven, Three, Four, Six, Five, Ten];
+        let mut table = rigged(rules(), &["a"], &draws).with_trainer(count::HI_LO);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("a", Command::Hit).expect("a's turn");
+        table.leave("a").expect("seated");
+        table.join("a", "a").expect("the seat is free again");
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.quiz("a").expect("a trainer");
+        let verdict = table.answer_count("a", 6, None).expect("just asked");
+        assert_eq!(verdict.reading.running, 6, "2, 5, 3 then 4, 6, 5 are shown");
+    }
+
+    #[test]
+    fn a_trainer_asks_for_the_count_of_the_old_shoe_as_it_shuffles() {
+        let mut table = seated(Rules { packs: 1, ..rules() }, &["a"]).with_trainer(count::HI_LO);
+        let mut kept = Count::new(count::HI_LO, 1);
+        loop {
+            let events = play_to_settlement(&mut table, "a");
+            let Some(at) = events.iter().position(|event| *event == Event::Shuffled) else {
+                events.iter().for_each(|event| kept.observe(event));
+                continue;
+            };
+            events[..at].iter().for_each(|event| kept.observe(event));
+            assert_eq!(events[at - 1], Event::CountAsked { true_count: true });
+            let true_count = kept.true_count().expect("hi-lo is balanced").round() as i32;
+            let verdict = table.answer_count("a", kept.running(), Some(true_count));
+            let verdict = verdict.expect("asked at the shuffle");
+            assert_eq!((verdict.running_right, verdict.true_right), (true, Some(true)));
+            break;
+        }
+    }
+
+    #[test]
+    fn early_surrender_answers_insurance_and_keeps_half_against_a_dealer_natural() {
+        let early = Rules { surrender: Surrender::Early, ..rules() };
+        let mut table = rigged(early, &["a"], &[Ten, Ace, Six, King]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(table.phase(), Phase::Insurance);
+        let events = table.apply("a", Command::Surrender).expect("before the peek");
+        assert_eq!(settled_outcome(&events), Some(Outcome::Surrender));
+        assert_eq!(table.bankroll("a"), Some(95));
+    }
+
+    #[test]
+    fn early_surrender_is_offered_before_a_ten_is_peeked_at_too() {
+        let early = Rules { surrender: Surrender::Early, ..rules() };
+        let mut table = rigged(early, &["a", "b"], &[Ten, Nine, King, Six, Eight, Ace]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        let events = table.apply("b", Command::Bet(10)).expect("bet accepted");
+        assert!(events.contains(&Event::SurrenderOffered));
+        assert!(!events.contains(&Event::InsuranceOffered));
+        assert_eq!(table.phase(), Phase::Insurance);
+        assert_eq!(table.apply("a", Command::Insure(5)), Err(TableError::NoInsuranceOffered));
+        assert_eq!(table.apply("a", Command::Insure(0)), Err(TableError::NoInsuranceOffered));
+        assert_eq!(table.apply("a", Command::EvenMoney), Err(TableError::NoInsuranceOffered));
+        table.apply("a", Command::Surrender).expect("before the peek");
+        let events = table.apply("b", Command::Decline).expect("b plays on");
+        assert_eq!(events[0], Event::SurrenderDeclined { name: "b".into() });
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Event::Settled { name, outcome: Outcome::Surrender, .. } if name == "a"
+        )));
+        assert_eq!((table.bankroll("a"), table.bankroll("b")), (Some(95), Some(90)));
+        assert_eq!(table.phase(), Phase::Betting);
+    }
+
+    #[test]
+    fn late_surrender_waits_for_the_peek() {
+        let late = Rules { surrender: Surrender::Late, ..rules() };
+        let mut table = rigged(late, &["a"], &[Ten, Ace, Six, King]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(table.apply("a", Command::Surrender), Err(TableError::WrongPhase));
+    }
+
+    #[test]
+    fn a_natural_is_never_surrendered_before_the_peek() {
+        let early = Rules { surrender: Surrender::Early, ..rules() };
+        let mut table = rigged(early, &["a"], &[Ace, Ace, King, Six]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(table.apply("a", Command::Surrender), Err(TableError::NaturalNotSurrendered));
+        let mut table = rigged(early, &["a", "b"], &[Ace, Nine, Ten, King, Eight, Six]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        let events = table.apply("b", Command::Bet(10)).expect("bet accepted");
+        assert!(events.contains(&Event::SurrenderDeclined { name: "a".into() }));
+        assert_eq!(table.apply("a", Command::Surrender), Err(TableError::InsuranceDecided));
+    }
+
+    #[test]
+    fn a_ten_up_is_peeked_and_a_dealer_natural_ends_the_round_before_anyone_acts() {
+        let mut table = rigged(rules(), &["a"], &[Nine, Ten, Nine, Ace]);
+        let events = table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert!(!events.iter().any(|event| matches!(event, Event::TurnStarted { .. })));
+        assert_eq!(settled_outcome(&events), Some(Outcome::Lose));
+        assert_eq!(table.phase(), Phase::Betting);
+    }
+
+    #[test]
+    fn a_ten_up_without_a_natural_is_checked_and_play_begins() {
+        let mut table = rigged(rules(), &["a"], &[Nine, Ten, Nine, Eight]);
+        let events = table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert!(events.contains(&Event::DealerChecked));
+        assert_eq!(table.phase(), Phase::PlayerTurns);
+    }
+
+    #[test]
+    fn a_european_dealer_takes_a_doubled_wager_with_a_late_natural() {
+        let european = Rules { hole_card: HoleCard::European, ..rules() };
+        let mut table = rigged(european, &["a"], &[Six, Ten, Five, Ten, Ace]);
+        let events = table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, Event::DealerShows { hole_card: false, .. })));
+        assert!(!events.contains(&Event::DealerChecked), "there is nothing to peek at");
+        let events = table.apply("a", Command::Double).expect("double on eleven");
+        assert_eq!(settled_outcome(&events), Some(Outcome::Lose));
+        assert_eq!(table.bankroll("a"), Some(80));
+    }
+
+    #[test]
+    fn original_bets_only_hands_back_the_double_against_a_european_natural() {
+        let obo = Rules { hole_card: HoleCard::European, original_bets_only: true, ..rules() };
+        let mut table = rigged(obo, &["a"], &[Six, Ten, Five, Ten, Ace]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        table.apply("a", Command::Double).expect("double on eleven");
+        assert_eq!(table.bankroll("a"), Some(90), "only the opening wager is lost");
+    }
+
+    #[test]
+    fn a_european_natural_still_draws_the_dealer_card_that_could_push_it() {
+        let european = Rules { hole_card: HoleCard::European, ..rules() };
+        let mut table = rigged(european, &["a"], &[Ace, Ten, King, Ace]);
+        let events = table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(settled_outcome(&events), Some(Outcome::Push));
+    }
+
+    #[test]
+    fn betting_opens_with_the_table_payout_announced() {
+        let six_to_five = Odds { win: 6, per: 5 };
+        let mut table = table(Rules { natural_pays: six_to_five, ..rules() });
+        let events = table.join("a", "Ada").expect("seat is free");
+        let opened = Event::BettingOpened { min_bet: 5, max_bet: 500, natural_pays: six_to_five };
+        assert_eq!(events.last(), Some(&opened));
+    }
+
+    #[test]
+    fn a_natural_is_paid_at_the_table_ratio() {
+        let six_to_five = Rules { natural_pays: Odds { win: 6, per: 5 }, ..rules() };
+        let mut table = rigged(six_to_five, &["a"], &[Ace, Nine, King, Eight]);
+        table.apply("a", Command::Bet(10)).expect("bet accepted");
+        assert_eq!(table.bankroll("a"), Some(112));
+    }
+
+    fn total(value: u16) -> Total {
+        Total { value, soft: false }
+    }
 }
@@ -1,7 +1,7 @@
 //! End-to-end rounds driven through the public table API with a seeded shoe,
 //! so the assertions below hold for real deals rather than hand-built hands.
 
-use blackjack_rust::rules::Rules;
+use blackjack_rust::rules::{HoleCard, Odds, Rules, Surrender};
 use blackjack_rust::table::{Command, Event, Phase, Table};
 use rand::rngs::StdRng;
 use rand::SeedableRng;
@@ -10,8 +10,17 @@ const HOUSE: Rules = Rules {
     packs: 6,
     dealer_hits_soft_17: false,
     min_bet: 5,
+    max_bet: 500,
     starting_bankroll: 200,
     min_players: 1,
+    max_rounds_sat_out: 5,
+    max_resplits: 2,
+    split_aces_one_card: true,
+    split_ace_21_is_natural: false,
+    surrender: Surrender::None,
+    hole_card: HoleCard::Peek,
+    original_bets_only: false,
+    natural_pays: Odds::THREE_TO_TWO,
 };
 
 /// Seats one player, bets, and stands until the round settles.
@@ -19,6 +28,9 @@ fn stand_pat(seed: u64) -> (Table<StdRng>, Vec<Event>) {
     let mut table = Table::new(HOUSE, StdRng::seed_from_u64(seed));
     let mut log = table.join("p1", "Ada").expect("the table is empty");
     log.extend(table.apply("p1", Command::Bet(20)).expect("the bet is legal"));
+    if table.phase() == Phase::Insurance {
+        log.extend(table.apply("p1", Command::Decline).expect("something is offered"));
+    }
     while table.phase() == Phase::PlayerTurns {
         log.extend(table.apply("p1", Command::Stand).expect("it is our turn"));
     }